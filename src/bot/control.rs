@@ -0,0 +1,146 @@
+use super::wizard::{format_time, parse_time};
+use super::Bot;
+use crate::locale::fill;
+use chrono::prelude::*;
+
+use telegram_bot::prelude::*;
+
+use telegram_bot::types::requests::send_message::SendMessage;
+use telegram_bot::types::{ParseMode, UserId};
+
+use telegram_bot::{Api, Error, Message};
+
+impl Bot {
+    /// Announces a change to the running poll in the group chat.
+    async fn announce(&self, api: &Api, text: String) -> Result<(), Error> {
+        let group_chat = self.roster.read().await.group_chat;
+        api.send(SendMessage::new(group_chat, text).parse_mode(ParseMode::Markdown))
+            .await?;
+        Ok(())
+    }
+
+    /// Checks the vote counters of the running poll against the stored
+    /// ballots and alerts every admin to any disagreement. Run at startup
+    /// and when a poll closes.
+    pub async fn reconcile(&self, api: &Api) -> Result<(), Error> {
+        let poll = self.poll.read().await.clone();
+        let mismatches = match self.db.lock().await.reconcile(poll.limit()) {
+            Ok(mismatches) => mismatches,
+            Err(err) => {
+                eprintln!("failed to reconcile the tally: {}", err);
+                return Ok(());
+            }
+        };
+        if mismatches.is_empty() {
+            return Ok(());
+        }
+        let admins: Vec<UserId> = self.admins.read().await.iter().copied().collect();
+        for admin in admins {
+            let m = self.messages_for(admin).await;
+            let mut text = fill(m.tally_mismatch, &[self.title(&poll)]);
+            for mismatch in mismatches.iter() {
+                text.push_str(&fill(m.mismatch_line, &[mismatch]));
+            }
+            api.send(SendMessage::new(admin, text).parse_mode(ParseMode::Markdown))
+                .await
+                .ok();
+        }
+        Ok(())
+    }
+
+    // ADMIN ONLY
+    /// Pauses or resumes voting. Ballots already cast are kept.
+    pub async fn handle_pause(
+        &self,
+        api: Api,
+        message: Message,
+        paused: bool,
+    ) -> Result<(), Error> {
+        if !self.check_admin_dm(&api, &message).await? {
+            return Ok(());
+        }
+        let m = self.messages(&message.from).await;
+        if !self.is_present() {
+            api.send(message.text_reply(m.no_poll)).await?;
+            return Ok(());
+        }
+        {
+            let mut poll = self.poll.write().await;
+            if poll.paused == paused {
+                drop(poll);
+                let reply = if paused { m.already_paused } else { m.not_paused };
+                api.send(message.text_reply(reply)).await?;
+                return Ok(());
+            }
+            poll.paused = paused;
+            let mut db = self.db.lock().await;
+            db.set_paused(paused).ok();
+            db.audit(message.from.id, if paused { "pause" } else { "resume" }, "")
+                .ok();
+        }
+        self.refresh_status(&api, true).await.ok();
+        let g = self.group_messages().await;
+        if paused {
+            api.send(message.text_reply(m.poll_paused)).await?;
+            self.announce(&api, g.poll_paused.to_string()).await?;
+        } else {
+            api.send(message.text_reply(m.poll_resumed)).await?;
+            self.announce(&api, g.poll_resumed.to_string()).await?;
+        }
+        Ok(())
+    }
+
+    // ADMIN ONLY
+    /// Moves the end of the running poll. Takes minutes from now or a Seoul
+    /// local time such as `2021-03-01 18:00`.
+    pub async fn handle_set_end(
+        &self,
+        api: Api,
+        message: Message,
+        command: String,
+    ) -> Result<(), Error> {
+        if !self.check_admin_dm(&api, &message).await? {
+            return Ok(());
+        }
+        let m = self.messages(&message.from).await;
+        if !self.is_present() {
+            api.send(message.text_reply(m.no_poll)).await?;
+            return Ok(());
+        }
+        let now = Utc::now().timestamp();
+        let arg = command.splitn(2, char::is_whitespace).nth(1).unwrap_or("").trim();
+        let end = match arg.parse::<i64>() {
+            Ok(minutes) => Some(now + minutes * 60),
+            Err(_) => parse_time(arg),
+        };
+        let changed = match end {
+            Some(end) if end > now => {
+                let mut poll = self.poll.write().await;
+                if end >= poll.start + 60 {
+                    let old = poll.end;
+                    poll.end = end;
+                    let mut db = self.db.lock().await;
+                    db.set_end(end).ok();
+                    db.audit(message.from.id, "set_end", &format!("{} -> {}", old, end))
+                        .ok();
+                    true
+                } else {
+                    false
+                }
+            }
+            _ => false,
+        };
+        if !changed {
+            api.send(message.text_reply(m.set_end_usage).parse_mode(ParseMode::Markdown))
+                .await?;
+            return Ok(());
+        }
+        let end = format_time(end.unwrap());
+        api.send(message.text_reply(&fill(m.end_changed, &[&end])))
+            .await?;
+        self.refresh_status(&api, true).await.ok();
+        let g = self.group_messages().await;
+        self.announce(&api, fill(g.end_changed, &[&end])).await?;
+        Ok(())
+    }
+}
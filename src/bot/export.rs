@@ -0,0 +1,116 @@
+use super::Bot;
+use crate::locale::fill;
+use crate::middlewares::db::PollRecord;
+
+use telegram_bot::prelude::*;
+
+use telegram_bot::types::ParseMode;
+
+use telegram_bot::{Api, Error, InputFileUpload, Message, SendDocument};
+
+/// Quotes a CSV field when it needs it.
+fn csv_field(s: &str) -> String {
+    if s.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// The record as CSV: the poll's fields one per row, then the candidate
+/// totals, then the ballots, each block after a header row and a blank line.
+pub fn to_csv(record: &PollRecord) -> String {
+    let mut csv = String::from("field,value\n");
+    let fields: [(&str, String); 14] = [
+        ("id", record.id.to_string()),
+        ("parent", record.parent.map(|p| p.to_string()).unwrap_or_default()),
+        ("title", record.title.clone()),
+        ("kind", record.kind.clone()),
+        ("method", record.method.clone()),
+        ("selections", record.selections.to_string()),
+        ("electorate", record.electorate.clone()),
+        ("rules", record.rules.clone()),
+        ("start", record.start.to_string()),
+        ("end", record.end.to_string()),
+        ("closed", record.closed.to_string()),
+        ("eligible", record.eligible.to_string()),
+        ("voters", record.voters.to_string()),
+        ("participants", record.participants.to_string()),
+    ];
+    for (name, value) in fields.iter() {
+        csv.push_str(&format!("{},{}\n", name, csv_field(value)));
+    }
+    csv.push_str("\nnumber,name,choice,votes,weighted\n");
+    for c in record.candidates.iter() {
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            c.number,
+            csv_field(&c.name),
+            c.choice,
+            c.votes,
+            c.weighted
+        ));
+    }
+    csv.push_str("\ntoken,sha3_256\n");
+    for b in record.ballots.iter() {
+        csv.push_str(&format!("{},{}\n", b.token, b.sha3_256));
+    }
+    csv
+}
+
+impl Bot {
+    // ADMIN ONLY
+    /// Sends the archived record of a closed poll as JSON and CSV documents.
+    /// Without an argument the most recently closed poll is exported.
+    pub async fn handle_export(
+        &self,
+        api: Api,
+        message: Message,
+        command: String,
+    ) -> Result<(), Error> {
+        if !self.check_admin_dm(&api, &message).await? {
+            return Ok(());
+        }
+        let m = self.messages(&message.from).await;
+        let arg = command.split_whitespace().nth(1);
+        let id = match arg.map(|a| a.parse::<i64>()) {
+            Some(Ok(id)) => Some(id),
+            Some(Err(_)) => {
+                api.send(message.text_reply(m.export_usage).parse_mode(ParseMode::Markdown))
+                    .await?;
+                return Ok(());
+            }
+            None => self.db.lock().await.last_closed().unwrap_or(None),
+        };
+        let record = match id {
+            Some(id) => self.db.lock().await.record(id).unwrap_or(None),
+            None => None,
+        };
+        let record = match record {
+            Some(record) => record,
+            None => {
+                let running = self.is_present() && id == Some(self.poll.read().await.id);
+                let reply = if running { m.export_running } else { m.export_not_found };
+                api.send(message.text_reply(reply)).await?;
+                return Ok(());
+            }
+        };
+        self.db
+            .lock()
+            .await
+            .audit(message.from.id, "export", &record.id.to_string())
+            .ok();
+
+        let json = serde_json::to_string_pretty(&record).unwrap_or_default();
+        let caption = fill(m.export_caption, &[&record.id, &record.title]);
+        for (data, ext) in [(json, "json"), (to_csv(&record), "csv")].iter() {
+            let file = InputFileUpload::with_data(
+                data.clone().into_bytes(),
+                format!("poll-{}.{}", record.id, ext),
+            );
+            api.send(SendDocument::new(message.chat.id(), file).caption(&caption))
+                .await?;
+        }
+        Ok(())
+    }
+}
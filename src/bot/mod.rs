@@ -0,0 +1,1604 @@
+use crate::config::Config;
+use crate::roster::Roster;
+use crate::constants::*;
+use crate::dialog::Dialog;
+use crate::locale::{escape_markdown, fill, Lang, Messages};
+use crate::middlewares::db::DbService;
+use crate::poll_token::PollToken;
+use crate::token_service::TokenService;
+use chrono::prelude::*;
+use chrono_tz::Asia::Seoul;
+use rand::prelude::*;
+use sha3::{Digest, Sha3_256};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+
+use telegram_bot::prelude::*;
+
+use telegram_bot::types::reply_markup::*;
+use telegram_bot::types::{CallbackQuery, MessageChat, MessageId, ParseMode, User, UserId};
+use telegram_bot::types::requests::send_message::SendMessage;
+
+use telegram_bot::{Api, Error, InputFileRef, Message, SendPhoto};
+
+mod control;
+mod export;
+mod notify;
+mod roster;
+mod rules;
+mod status;
+mod wizard;
+
+pub use export::to_csv;
+pub use rules::Rules;
+use rules::TiePolicy;
+
+/// What picking a ballot entry means.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Choice {
+    Candidate,
+    /// None of the above: the voter rejects every candidate.
+    Nota,
+    /// The voter takes part without supporting anyone.
+    Abstain,
+}
+
+impl Choice {
+    pub fn parse(s: &str) -> Option<Choice> {
+        match s {
+            "candidate" => Some(Choice::Candidate),
+            "nota" => Some(Choice::Nota),
+            "abstain" => Some(Choice::Abstain),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Choice::Candidate => "candidate",
+            Choice::Nota => "nota",
+            Choice::Abstain => "abstain",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Candidate {
+    pub name: String,
+    pub choice: Choice,
+    /// Short platform statement shown from the ballot's profile button.
+    pub statement: Option<String>,
+    /// Telegram `file_id` of the candidate photo.
+    pub photo: Option<String>,
+}
+
+impl Candidate {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            choice: Choice::Candidate,
+            statement: None,
+            photo: None,
+        }
+    }
+
+    /// NOTA and Abstain entries. A voter holding one of them holds nothing
+    /// else.
+    pub fn is_special(&self) -> bool {
+        self.choice != Choice::Candidate
+    }
+}
+
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// How many candidates a voter may pick.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    /// Up to `selections` candidates; picking one more replaces the oldest.
+    Approval,
+    /// Exactly one candidate.
+    Single,
+}
+
+impl Method {
+    pub fn parse(s: &str) -> Option<Method> {
+        match s {
+            "approval" => Some(Method::Approval),
+            "single" => Some(Method::Single),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Approval => "approval",
+            Method::Single => "single",
+        }
+    }
+}
+
+/// What a poll decides.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// Electing candidates.
+    Election,
+    /// For/Against/Abstain on a single question.
+    Referendum,
+}
+
+impl Kind {
+    pub fn parse(s: &str) -> Option<Kind> {
+        match s {
+            "election" => Some(Kind::Election),
+            "referendum" => Some(Kind::Referendum),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Kind::Election => "election",
+            Kind::Referendum => "referendum",
+        }
+    }
+}
+
+/// Who may vote in a poll.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Electorate {
+    Users,
+    Admins,
+}
+
+impl Electorate {
+    pub fn parse(s: &str) -> Option<Electorate> {
+        match s {
+            "users" => Some(Electorate::Users),
+            "admins" => Some(Electorate::Admins),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Electorate::Users => "users",
+            Electorate::Admins => "admins",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PollService {
+    pub title: String,
+    pub candidates: Vec<Candidate>,
+    pub start: i64,
+    pub end: i64,
+    pub key: String,
+    /// Secret behind the seed of a tie-breaking draw, revealed with the
+    /// result. See [`rules::draw_seed`].
+    pub draw_secret: String,
+    /// Ballots per candidate.
+    pub votes: Vec<i64>,
+    /// Ballots per candidate, each counted with its voter's weight.
+    pub weighted: Vec<i64>,
+    pub kind: Kind,
+    pub method: Method,
+    pub selections: usize,
+    pub electorate: Electorate,
+    /// Voters eligible for this poll, frozen when it was created.
+    pub electors: HashMap<UserId, String>,
+    /// Weights of electors other than 1, frozen with them.
+    pub weights: HashMap<UserId, i64>,
+    pub rules: Rules,
+    /// Whether the end has already been pushed back to reach quorum.
+    pub extended: bool,
+    /// Whether voters have been told the poll is open.
+    pub announced: bool,
+    /// The group message kept up to date with the poll's status.
+    pub status: Option<i64>,
+    /// Ballots can't be cast or cleared while an admin has paused the poll.
+    pub paused: bool,
+    /// Row of the poll in the `polls` registry, assigned when it is opened.
+    pub id: i64,
+    /// The poll this one is a runoff of.
+    pub parent: Option<i64>,
+}
+
+impl PollService {
+    pub fn new(title: String, candidates: Vec<Candidate>, start: i64, end: i64) -> Self {
+        let mut rng = thread_rng();
+        let key: [u8; AES_KEY_LEN / 2] = rng.gen();
+        let draw_secret: [u8; 16] = rng.gen();
+        Self {
+            title,
+            candidates: candidates.clone(),
+            start,
+            end,
+            key: hex::encode(key),
+            draw_secret: hex::encode(draw_secret),
+            votes: vec![0; candidates.len()],
+            weighted: vec![0; candidates.len()],
+            kind: Kind::Election,
+            method: Method::Approval,
+            selections: 3,
+            electorate: Electorate::Users,
+            electors: HashMap::new(),
+            weights: HashMap::new(),
+            rules: Rules::default(),
+            extended: false,
+            announced: false,
+            status: None,
+            paused: false,
+            id: 0,
+            parent: None,
+        }
+    }
+
+    /// A For/Against/Abstain poll, with the options named in `m`.
+    pub fn referendum(title: String, start: i64, end: i64, m: &Messages) -> Self {
+        let options = vec![
+            Candidate::new(m.option_for.to_string()),
+            Candidate::new(m.option_against.to_string()),
+            Candidate::new(m.option_abstain.to_string()),
+        ];
+        let mut poll = Self::new(title, options, start, end);
+        poll.kind = Kind::Referendum;
+        poll.method = Method::Single;
+        poll.selections = 1;
+        poll
+    }
+
+    /// Maximum number of ballots a single voter can hold at once.
+    pub fn limit(&self) -> usize {
+        match self.method {
+            Method::Approval => self.selections,
+            Method::Single => 1,
+        }
+    }
+
+    /// SHA3-256 of the draw secret, shown while the poll runs.
+    pub fn draw_commitment(&self) -> String {
+        hex::encode(Sha3_256::digest(self.draw_secret.as_bytes()))
+    }
+
+    pub fn weight(&self, id: UserId) -> i64 {
+        *self.weights.get(&id).unwrap_or(&1)
+    }
+
+    pub fn is_weighted(&self) -> bool {
+        self.weights.values().any(|w| *w != 1)
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.start <= Utc::now().timestamp()
+    }
+}
+
+/// Takes the electors of `poll`, and their weights, from `roster`.
+fn freeze_electors(poll: &mut PollService, roster: &Roster) {
+    poll.electors = match poll.electorate {
+        Electorate::Users => roster.active_users().into_iter().collect(),
+        Electorate::Admins => roster.admins.iter().cloned().collect(),
+    };
+    poll.weights = poll
+        .electors
+        .keys()
+        .map(|id| (*id, roster.weight(*id)))
+        .filter(|(_, w)| *w != 1)
+        .collect();
+}
+
+/// Shared bot state. Every field is behind its own lock so independent
+/// updates can be handled concurrently; lock order is `poll` -> `db`.
+pub struct Bot {
+    /// Display name of the bot, used where a poll has no title of its own.
+    name: String,
+    /// Link that opens a private chat with the bot.
+    link: String,
+    is_present: AtomicBool,
+    db: Mutex<DbService>,
+    poll: RwLock<PollService>,
+    //user_token: TokenService,
+    admin_token: Mutex<TokenService>,
+    config: Config,
+    roster: RwLock<Roster>,
+    admins: RwLock<HashSet<UserId>>,
+    ballots: Mutex<HashMap<UserId, Arc<Mutex<()>>>>,
+    dialogs: Mutex<HashMap<UserId, Dialog>>,
+    /// Held while a user's dialog is taken out of `dialogs` and advanced,
+    /// so their next message waits for it instead of finding no dialog.
+    dialog_locks: Mutex<HashMap<UserId, Arc<Mutex<()>>>>,
+    /// When the group status message was last edited, and its text then.
+    status_edit: Mutex<(i64, String)>,
+}
+
+impl Bot {
+    pub fn new(me: User, config: Config) -> Self {
+        let branding = config.bot.clone();
+        let name = branding
+            .as_ref()
+            .and_then(|b| b.name.clone())
+            .unwrap_or(me.first_name);
+        let link = branding.and_then(|b| b.url).unwrap_or(format!(
+            "https://t.me/{}",
+            me.username.unwrap_or_default()
+        ));
+        let mut db = DbService::new().unwrap();
+        db.import_config(&config).unwrap();
+        let roster = db.roster().unwrap();
+        let mut admins: HashSet<UserId> = HashSet::new();
+        for (u, _) in roster.admins.iter() {
+            admins.insert(*u);
+        }
+        let mut poll = db.load().unwrap();
+        // A poll that was already running when electors started being
+        // stored has none; freeze them from the roster as of now.
+        if db.is_present().unwrap() && poll.electors.is_empty() {
+            freeze_electors(&mut poll, &roster);
+            for (id, name) in poll.electors.iter() {
+                db.add_elector(*id, name, poll.weight(*id)).unwrap();
+            }
+        }
+        Self {
+            name,
+            link,
+            is_present: AtomicBool::new(db.is_present().unwrap()),
+            db: Mutex::new(db),
+            poll: RwLock::new(poll),
+            //user_token: TokenService::new(),
+            admin_token: Mutex::new(TokenService::new()),
+            config,
+            roster: RwLock::new(roster),
+            admins: RwLock::new(admins),
+            ballots: Mutex::new(HashMap::new()),
+            dialogs: Mutex::new(HashMap::new()),
+            dialog_locks: Mutex::new(HashMap::new()),
+            status_edit: Mutex::new((0, String::new())),
+        }
+    }
+
+    /// Records that the sender of a private message can be sent direct
+    /// messages.
+    pub async fn note_private(&self, message: &Message) {
+        if let MessageChat::Private(_) = message.chat {
+            self.db.lock().await.mark_started(message.from.id).ok();
+        }
+    }
+
+    /// Deep link that opens the ballot of `poll` in a private chat with the
+    /// bot. See [`Bot::handle_start`].
+    fn vote_link(&self, poll: &PollService) -> String {
+        format!("{}?start=vote_{}", self.link.trim_end_matches('/'), poll.id)
+    }
+
+    fn title<'a>(&'a self, poll: &'a PollService) -> &'a String {
+        if poll.title.is_empty() {
+            &self.name
+        } else {
+            &poll.title
+        }
+    }
+
+    pub fn is_present(&self) -> bool {
+        self.is_present.load(Ordering::SeqCst)
+    }
+
+    pub async fn poll_end(&self) -> i64 {
+        self.poll.read().await.end
+    }
+
+    pub async fn is_paused(&self) -> bool {
+        self.poll.read().await.paused
+    }
+
+    async fn is_admin(&self, id: UserId) -> bool {
+        self.admins.read().await.contains(&id)
+    }
+
+    /// Replies with the matching error unless `message` comes from an admin
+    /// in a private chat.
+    async fn check_admin_dm(&self, api: &Api, message: &Message) -> Result<bool, Error> {
+        let m = self.messages(&message.from).await;
+        if let MessageChat::Private(_) = message.chat {
+            if self.is_admin(message.from.id).await {
+                return Ok(true);
+            }
+            api.send(
+                message
+                    .text_reply(m.admin_only)
+                    .parse_mode(ParseMode::Markdown),
+            )
+            .await?;
+        } else {
+            api.send(
+                message
+                    .text_reply(m.admin_private_only)
+                    .parse_mode(ParseMode::Markdown),
+            )
+            .await?;
+        }
+        Ok(false)
+    }
+
+    fn is_eligible(&self, poll: &PollService, id: UserId) -> bool {
+        poll.electors.contains_key(&id)
+    }
+
+    /// Stores `poll` as the running poll, freezing its electors from the
+    /// current roster unless it already carries them. Returns `false` if a
+    /// poll is already running, and the error if it couldn't be stored, in
+    /// which case no poll is running afterwards.
+    async fn open_poll(&self, mut poll: PollService) -> Result<bool, sqlite::Error> {
+        if self.is_present() {
+            return Ok(false);
+        }
+        if poll.kind == Kind::Election {
+            let g = self.group_messages().await;
+            for (on, name, choice) in [
+                (poll.rules.nota, g.option_nota, Choice::Nota),
+                (poll.rules.abstain, g.option_abstain, Choice::Abstain),
+            ]
+            .iter()
+            {
+                if *on {
+                    let mut special = Candidate::new(name.to_string());
+                    special.choice = *choice;
+                    poll.candidates.push(special);
+                }
+            }
+            poll.votes.resize(poll.candidates.len(), 0);
+            poll.weighted.resize(poll.candidates.len(), 0);
+        }
+        if poll.electors.is_empty() {
+            freeze_electors(&mut poll, &*self.roster.read().await);
+        }
+        // `is_present` is only switched on together with the poll it stands
+        // for, so nobody sees it set while `poll` still holds the last one.
+        let mut current = self.poll.write().await;
+        if self.is_present() {
+            return Ok(false);
+        }
+        let mut db = self.db.lock().await;
+        match db.create(poll.clone()) {
+            Ok(id) => {
+                poll.id = id;
+                *current = poll;
+                self.is_present.store(true, Ordering::SeqCst);
+                Ok(true)
+            }
+            Err(err) => {
+                db.clear().ok();
+                Err(err)
+            }
+        }
+    }
+
+    /// Records in the audit trail that `admin` opened the running poll.
+    async fn audit_open(&self, admin: UserId) {
+        let detail = {
+            let poll = self.poll.read().await;
+            format!("{},{}", poll.id, poll.title)
+        };
+        self.db.lock().await.audit(admin, "create", &detail).ok();
+    }
+
+    /// The user's `/lang` choice, falling back to their Telegram client language.
+    async fn lang(&self, user: &User) -> Lang {
+        if let Ok(Some(code)) = self.db.lock().await.lang(user.id) {
+            if let Some(lang) = Lang::parse(&code) {
+                return lang;
+            }
+        }
+        match user.language_code {
+            Some(ref code) => Lang::from_code(code),
+            None => Lang::default(),
+        }
+    }
+
+    /// Messages for a user known only by id, as when the bot writes first.
+    /// Falls back to the group language without a `/lang` choice.
+    async fn messages_for(&self, id: UserId) -> &'static Messages {
+        if let Ok(Some(code)) = self.db.lock().await.lang(id) {
+            if let Some(lang) = Lang::parse(&code) {
+                return lang.messages();
+            }
+        }
+        self.group_messages().await
+    }
+
+    async fn messages(&self, user: &User) -> &'static Messages {
+        self.lang(user).await.messages()
+    }
+
+    async fn group_messages(&self) -> &'static Messages {
+        match self.config.language {
+            Some(ref code) => Lang::from_code(code).messages(),
+            None => Lang::default().messages(),
+        }
+    }
+
+    /// Ballots of a single user are serialized so the approval limit can't
+    /// be raced by clicking several buttons at once.
+    async fn ballot_lock(&self, id: UserId) -> Arc<Mutex<()>> {
+        self.ballots
+            .lock()
+            .await
+            .entry(id)
+            .or_insert_with(|| Arc::new(Mutex::new(())))
+            .clone()
+    }
+
+    pub(super) async fn dialog_lock(&self, id: UserId) -> Arc<Mutex<()>> {
+        self.dialog_locks
+            .lock()
+            .await
+            .entry(id)
+            .or_insert_with(|| Arc::new(Mutex::new(())))
+            .clone()
+    }
+
+    // ADMIN ONLY
+    pub async fn handle_remove_poll<'p>(&self, api: Api, message: Message) -> Result<(), Error> {
+        let m = self.messages(&message.from).await;
+        if self.is_admin(message.from.id).await {
+            let id = self.poll.read().await.id;
+            if self.finish_poll(&api, message.chat.id(), m).await? {
+                self.db
+                    .lock()
+                    .await
+                    .audit(message.from.id, "remove", &id.to_string())
+                    .ok();
+            } else {
+                api.send(message.text_reply(m.no_poll))
+                    .await?;
+            }
+        } else {
+            api.send(
+                message
+                    .text_reply(m.admin_only)
+                    .parse_mode(ParseMode::Markdown),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    // ADMIN ONLY
+    pub async fn handle_create_poll(
+        &self,
+        api: Api,
+        message: Message,
+        command: String,
+    ) -> Result<(), Error> {
+        let m = self.messages(&message.from).await;
+        let splited: Vec<String> = command.split_whitespace().map(|s| s.to_string()).collect();
+        if let Some(parms) = splited.get(1..) {
+            if parms.len() > 0 {
+                if self.is_admin(message.from.id).await {
+                    let mut rules = Rules::default();
+                    let mut kind = Kind::Election;
+                    let mut names = vec![];
+                    for parm in parms {
+                        if !parm.contains('=') {
+                            names.push(parm.clone());
+                        } else if let Some(k) = parm.strip_prefix("type=").and_then(Kind::parse) {
+                            kind = k;
+                        } else if !rules.set(parm) {
+                            api.send(
+                                message
+                                    .text_reply(&fill(m.invalid_rule, &[parm]))
+                                    .parse_mode(ParseMode::Markdown),
+                            )
+                            .await?;
+                            return Ok(());
+                        }
+                    }
+                    if names.is_empty() {
+                        self.handle_unknown_command(api.clone(), message.clone())
+                            .await?;
+                        return Ok(());
+                    }
+                    let title = names.remove(0).replace("/S/", " ");
+                    if let Some(times) = names.pop().and_then(|t| t.parse::<i64>().ok()) {
+                        if names.len() == 0 && kind == Kind::Election {
+                            api.send(
+                                message
+                                    .text_reply(m.need_candidate)
+                                    .parse_mode(ParseMode::Markdown),
+                            )
+                            .await?;
+                        } else {
+                            let candidates: Vec<Candidate> = names
+                                .iter()
+                                .map(|c| Candidate::new(c.replace("/S/", " ")))
+                                .collect();
+                            if times <= 0 {
+                                api.send(
+                                    message
+                                        .text_reply(
+                                            m.min_duration,
+                                        )
+                                        .parse_mode(ParseMode::Markdown),
+                                )
+                                .await?;
+                            } else {
+                                let start = Utc::now().timestamp();
+                                let mut poll = match kind {
+                                    Kind::Election => {
+                                        PollService::new(title, candidates, start, start + times * 60)
+                                    }
+                                    Kind::Referendum => PollService::referendum(
+                                        title,
+                                        start,
+                                        start + times * 60,
+                                        self.group_messages().await,
+                                    ),
+                                };
+                                poll.rules = rules;
+                                match self.open_poll(poll).await {
+                                    Ok(true) => {
+                                        self.audit_open(message.from.id).await;
+                                        self.handle_poll(api.clone(), message.clone()).await?;
+                                        self.announce_poll(&api).await?;
+                                    }
+                                    Ok(false) => {
+                                        api.send(
+                                            message
+                                                .text_reply(
+                                                    m.already_running,
+                                                )
+                                                .parse_mode(ParseMode::Markdown),
+                                        )
+                                        .await?;
+                                    }
+                                    Err(err) => {
+                                        eprintln!("failed to store the new poll: {}", err);
+                                        api.send(message.text_reply(m.poll_not_saved)).await?;
+                                    }
+                                }
+                            }
+                        }
+                    } else {
+                        self.handle_unknown_command(api.clone(), message.clone())
+                            .await?;
+                    }
+                } else {
+                    api.send(
+                        message
+                            .text_reply(m.admin_only)
+                            .parse_mode(ParseMode::Markdown),
+                    )
+                    .await?;
+                }
+            } else {
+                self.start_wizard(api.clone(), message.clone()).await?;
+            }
+        } else {
+            self.handle_unknown_command(api.clone(), message.clone())
+                .await?;
+        }
+        Ok(())
+    }
+
+    fn method_line(poll: &PollService, m: &Messages) -> String {
+        match (poll.kind, poll.method) {
+            (Kind::Referendum, _) => fill(m.pass_line, &[&poll.rules.pass.name(m)]),
+            (_, Method::Approval) => fill(m.method_approval, &[&poll.selections]),
+            (_, Method::Single) => m.method_single.to_string(),
+        }
+    }
+
+    /// Title line of a poll, followed by its candidates or options.
+    fn poll_header(&self, poll: &PollService, m: &Messages) -> String {
+        let mut header = match poll.kind {
+            Kind::Election => {
+                let count = poll.candidates.iter().filter(|c| !c.is_special()).count();
+                fill(m.candidate_count, &[self.title(poll), &count])
+            }
+            Kind::Referendum => fill(m.referendum_header, &[self.title(poll)]),
+        };
+        if let Some(parent) = poll.parent {
+            header.push_str(&fill(m.runoff_line, &[&parent]));
+        }
+        for i in 0..poll.candidates.len() {
+            let is_candidate = poll.kind == Kind::Election && !poll.candidates[i].is_special();
+            header.push_str(&if is_candidate {
+                fill(m.candidate_line, &[&(i + 1), &poll.candidates[i]])
+            } else {
+                fill(m.option_line, &[&(i + 1), &poll.candidates[i]])
+            });
+        }
+        header
+    }
+
+    /// Overview of the running poll, shared by `/poll` and the creation wizard.
+    async fn poll_summary(&self, m: &Messages) -> (String, InlineKeyboardMarkup) {
+        let poll = self.poll.read().await.clone();
+        let (current, ever) = self.db.lock().await.turnout().unwrap_or((0, 0));
+        let mut reply_msg = self.poll_header(&poll, m);
+        let mut markup = InlineKeyboardMarkup::new();
+        if poll.paused {
+            reply_msg.push_str(m.paused_line);
+        }
+        reply_msg.push_str(&Self::method_line(&poll, m));
+        reply_msg.push_str(m.how_to_vote);
+        reply_msg.push_str(&fill(
+            m.start_time,
+            &[&Seoul.from_utc_datetime(&NaiveDateTime::from_timestamp(poll.start, 0))], //.unwrap()
+        ));
+        reply_msg.push_str(&fill(
+            m.end_time,
+            &[&Seoul.from_utc_datetime(&NaiveDateTime::from_timestamp(poll.end, 0))], //.unwrap()
+        ));
+        let eligible = poll.electors.len();
+        let percent = |n: usize| format!("{:.1}", (n as f64) / (eligible.max(1) as f64) * 100.0);
+        reply_msg.push_str(&fill(m.turnout, &[&current, &eligible, &percent(current)]));
+        reply_msg.push_str(&fill(m.participation, &[&ever, &eligible, &percent(ever)]));
+        reply_msg.push_str(&poll.rules.describe(m, eligible, poll.kind));
+        if poll.kind == Kind::Election && poll.rules.tie == TiePolicy::Draw {
+            reply_msg.push_str(&fill(m.draw_commitment_line, &[&poll.draw_commitment()]));
+        }
+        markup.add_row(vec![InlineKeyboardButton::url(
+            m.go_vote,
+            &self.vote_link(&poll),
+        )]);
+        (reply_msg, markup)
+    }
+
+    /// Shows the running poll. In the group, an existing status message is
+    /// refreshed and pointed to instead of posting another snapshot.
+    pub async fn handle_poll(&self, api: Api, message: Message) -> Result<(), Error> {
+        let m = self.messages(&message.from).await;
+        let status = self.poll.read().await.status;
+        let group_chat = self.roster.read().await.group_chat;
+        if let Some(id) = status.filter(|_| self.is_present() && message.chat.id() == group_chat) {
+            self.refresh_status(&api, false).await.ok();
+            api.send(SendMessage::new(group_chat, m.status_above).reply_to(MessageId::new(id)))
+                .await?;
+        } else if self.is_present() {
+            let (reply_msg, markup) = self.poll_summary(m).await;
+            api.send(
+                message
+                    .text_reply(&reply_msg)
+                    .reply_markup(ReplyMarkup::InlineKeyboardMarkup(markup))
+                    .parse_mode(ParseMode::Markdown),
+            )
+            .await?;
+        } else {
+            api.send(message.text_reply(m.no_poll))
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn handle_vote(&self, api: Api, message: Message) -> Result<(), Error> {
+        let m = self.messages(&message.from).await;
+        if let MessageChat::Private(_) = message.chat {
+            if self.is_present() {
+                let poll = self.poll.read().await.clone();
+                let mut reply_msg = self.poll_header(&poll, m);
+                let mut markup = InlineKeyboardMarkup::new();
+                let mut rng = thread_rng();
+                let priv_key: [u8; AES_KEY_LEN / 2] = rng.gen();
+                for i in 0..poll.candidates.len() {
+                    let vote = format!("/vote {} {}", i + 1, hex::encode(priv_key));
+                    let is_candidate =
+                        poll.kind == Kind::Election && !poll.candidates[i].is_special();
+                    markup.add_row(if is_candidate {
+                        vec![
+                            InlineKeyboardButton::callback(&fill(m.vote_button, &[&(i + 1)]), &vote),
+                            InlineKeyboardButton::callback(
+                                m.profile_button,
+                                &format!("/profile {}", i + 1),
+                            ),
+                        ]
+                    } else {
+                        vec![InlineKeyboardButton::callback(&poll.candidates[i].name, &vote)]
+                    });
+                }
+                markup.add_row(vec![
+                    InlineKeyboardButton::callback(
+                        m.check_button,
+                        &format!("/check {}", hex::encode(priv_key)),
+                    ),
+                    InlineKeyboardButton::callback(
+                        m.clear_button,
+                        &format!("/clear {}", hex::encode(priv_key)),
+                    ),
+                ]);
+                reply_msg.push_str(&Self::method_line(&poll, m));
+                reply_msg.push_str(m.how_to_vote);
+                reply_msg.push_str(&fill(
+                    m.start_time,
+                    &[&Seoul.from_utc_datetime(&NaiveDateTime::from_timestamp(poll.start, 0))], //.unwrap()
+                ));
+                reply_msg.push_str(&fill(
+                    m.end_time,
+                    &[&Seoul.from_utc_datetime(&NaiveDateTime::from_timestamp(poll.end, 0))], //.unwrap()
+                ));
+                api.send(
+                    message
+                        .text_reply(&reply_msg)
+                        .reply_markup(ReplyMarkup::InlineKeyboardMarkup(markup))
+                        .parse_mode(ParseMode::Markdown),
+                )
+                .await?;
+            } else {
+                api.send(message.text_reply(m.no_poll))
+                    .await?;
+            }
+        } else {
+            api.send(message.text_reply(m.private_only))
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn handle_unknown_command(&self, api: Api, message: Message) -> Result<(), Error> {
+        let m = self.messages(&message.from).await;
+        api.send(message.text_reply(m.unknown_command).parse_mode(ParseMode::Markdown)).await?;
+        Ok(())
+    }
+
+    pub async fn handle_help(&self, api: Api, message: Message) -> Result<(), Error> {
+        let m = self.messages(&message.from).await;
+        api.send(message.text_reply(&fill(m.help, &[&self.name])).parse_mode(ParseMode::Markdown))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn handle_about(&self, api: Api, message: Message) -> Result<(), Error> {
+        let m = self.messages(&message.from).await;
+        let version = format!("{}.{}.{}", pkg_version_major!(), pkg_version_minor!(), pkg_version_patch!());
+        api.send(message.text_reply(&fill(m.about, &[&self.name, &version])).parse_mode(ParseMode::Markdown))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn handle_admin_help(&self, api: Api, message: Message) -> Result<(), Error> {
+        let m = self.messages(&message.from).await;
+        api.send(
+            message
+                .text_reply(&fill(m.admin_help, &[&self.name]))
+                .parse_mode(ParseMode::Markdown),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn handle_check_callback(
+        &self,
+        api: Api,
+        callback: CallbackQuery,
+    ) -> Result<(), Error> {
+        let m = self.messages(&callback.from).await;
+        let command = callback.data.clone().unwrap();
+        let splited: Vec<String> = command.split_whitespace().map(|s| s.to_string()).collect();
+        if self.is_present() {
+            let poll = self.poll.read().await.clone();
+            if self.is_eligible(&poll, callback.from.id) {
+                let mut cnt = 0;
+                let nonce = self.config.security.nonce.clone();
+                let list = self.db.lock().await.fetch_token(callback.from.id).unwrap();
+                let mut res = String::new();
+                for t in list.clone() {
+                    if let Ok(vote) = t.decrypt(nonce.clone(), poll.key.clone(), splited[1].clone())
+                    {
+                        res.push_str(&fill(
+                            m.check_line,
+                            &[&(cnt + 1), &poll.candidates[vote as usize - 1]],
+                        ));
+                        cnt += 1;
+                    }
+                }
+                if res.is_empty() {
+                    api.send(callback.answer(m.no_ballot))
+                        .await?;
+                } else {
+                    api.send(callback.answer(&res)).await?;
+                }
+            } else {
+                api.send(callback.answer(m.not_eligible))
+                    .await?;
+            }
+        } else {
+            api.send(callback.answer(m.not_running))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Removes one of the caller's ballots. Must be called with the caller's
+    /// ballot lock held. The counters only change if the ballot was removed
+    /// from the database.
+    async fn retract(&self, poll_id: i64, token: PollToken, vote: i64) -> bool {
+        let mut poll = self.poll.write().await;
+        // The poll may have closed or been replaced since the caller looked.
+        if !self.is_present() || poll.id != poll_id {
+            return false;
+        }
+        let i = vote as usize - 1;
+        let weight = poll.weight(token.user_id);
+        let (votes, weighted) = (poll.votes[i] - 1, poll.weighted[i] - weight);
+        let stored = self.db.lock().await.retract(&token, vote as usize, votes, weighted);
+        if stored.is_err() {
+            return false;
+        }
+        poll.votes[i] = votes;
+        poll.weighted[i] = weighted;
+        true
+    }
+
+    /// Adds a ballot for `target`. Must be called with the caller's ballot
+    /// lock held. The counters only change if the ballot was stored.
+    async fn cast(&self, poll_id: i64, token: PollToken, target: i64) -> bool {
+        let mut poll = self.poll.write().await;
+        // The poll may have closed or been replaced since the caller looked.
+        if !self.is_present() || poll.id != poll_id {
+            return false;
+        }
+        let i = target as usize - 1;
+        let weight = poll.weight(token.user_id);
+        let (votes, weighted) = (poll.votes[i] + 1, poll.weighted[i] + weight);
+        let stored = self.db.lock().await.cast(&token, target as usize, votes, weighted);
+        if stored.is_err() {
+            return false;
+        }
+        poll.votes[i] = votes;
+        poll.weighted[i] = weighted;
+        true
+    }
+
+    pub async fn handle_clear_callback(
+        &self,
+        api: Api,
+        callback: CallbackQuery,
+    ) -> Result<(), Error> {
+        let m = self.messages(&callback.from).await;
+        let command = callback.data.clone().unwrap();
+        let splited: Vec<String> = command.split_whitespace().map(|s| s.to_string()).collect();
+        if self.is_present() {
+            let poll = self.poll.read().await.clone();
+            if poll.paused {
+                api.send(callback.answer(m.paused)).await?;
+            } else if self.is_eligible(&poll, callback.from.id) {
+                let lock = self.ballot_lock(callback.from.id).await;
+                let _guard = lock.lock().await;
+                let nonce = self.config.security.nonce.clone();
+                let list = self.db.lock().await.fetch_token(callback.from.id).unwrap();
+                for t in list.clone() {
+                    if let Ok(vote) = t.decrypt(nonce.clone(), poll.key.clone(), splited[1].clone()) {
+                        self.retract(poll.id, t, vote).await;
+                    }
+                }
+                api.send(callback.answer(m.cleared))
+                    .await?;
+            } else {
+                api.send(callback.answer(m.not_eligible))
+                    .await?;
+            }
+        } else {
+            api.send(callback.answer(m.not_running))
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn handle_vote_callback(
+        &self,
+        api: Api,
+        callback: CallbackQuery,
+    ) -> Result<(), Error> {
+        let m = self.messages(&callback.from).await;
+        let command = callback.data.clone().unwrap();
+        let splited: Vec<String> = command.split_whitespace().map(|s| s.to_string()).collect();
+        if let Ok(target) = splited[1].parse::<i64>() {
+            let poll = self.poll.read().await.clone();
+            if self.is_present() && !poll.is_started() {
+                api.send(callback.answer(m.not_started)).await?;
+            } else if self.is_present() && poll.paused {
+                api.send(callback.answer(m.paused)).await?;
+            } else if self.is_present() && (target < 1 || target as usize > poll.candidates.len()) {
+                api.send(callback.answer(m.stale_ballot)).await?;
+            } else if self.is_present() {
+                if self.is_eligible(&poll, callback.from.id) {
+                    let lock = self.ballot_lock(callback.from.id).await;
+                    let _guard = lock.lock().await;
+                    let nonce = self.config.security.nonce.clone();
+                    let mut cnt = 0;
+                    let list = self.db.lock().await.fetch_token(callback.from.id).unwrap();
+                    for t in list.clone() {
+                        if let Ok(_) = t.decrypt(nonce.clone(), poll.key.clone(), splited[2].clone())
+                        {
+                            cnt += 1;
+                        }
+                    }
+                    if cnt == list.len() {
+                        let mut r = None;
+                        for t in list.clone() {
+                            if let Ok(vote) =
+                                t.decrypt(nonce.clone(), poll.key.clone(), splited[2].clone())
+                            {
+                                if vote == target {
+                                    r = Some(self.retract(poll.id, t, vote).await);
+                                    break;
+                                }
+                            }
+                        }
+                        if let Some(retracted) = r {
+                            let reply = if retracted { m.retracted } else { m.ballot_failed };
+                            api.send(callback.answer(reply)).await?;
+                        } else {
+                            // NOTA and Abstain can't be held together with
+                            // anything else.
+                            let special = poll.candidates[target as usize - 1].is_special();
+                            // A ballot that can't be removed would leave the
+                            // voter over the limit, so nothing is cast then.
+                            let mut retracted = true;
+                            let mut held = vec![];
+                            for t in list.clone() {
+                                if let Ok(vote) = t.decrypt(
+                                    nonce.clone(),
+                                    poll.key.clone(),
+                                    splited[2].clone(),
+                                ) {
+                                    if special || poll.candidates[vote as usize - 1].is_special() {
+                                        retracted &= self.retract(poll.id, t, vote).await;
+                                    } else {
+                                        held.push((t, vote));
+                                    }
+                                }
+                            }
+                            if retracted && held.len() >= poll.limit() {
+                                let (t, vote) = held.remove(0);
+                                retracted = self.retract(poll.id, t, vote).await;
+                            }
+                            let poll_token = PollToken::new(
+                                nonce.clone(),
+                                poll.key.clone(),
+                                splited[2].clone(),
+                                target,
+                                callback.from.id,
+                                MessageId::new(0),
+                            );
+                            if retracted && self.cast(poll.id, poll_token, target).await {
+                                api.send(callback.answer(&fill(m.voted, &[&poll.candidates[target as usize - 1]]))).await?;
+                            } else {
+                                api.send(callback.answer(m.ballot_failed)).await?;
+                            }
+                        }
+                    } else {
+                        api.send(
+                            callback.answer(m.stale_ballot),
+                        )
+                        .await?;
+                    }
+                } else {
+                    api.send(callback.answer(m.not_eligible))
+                        .await?;
+                }
+            } else {
+                api.send(callback.answer(m.not_running))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn handle_accept(&self, api: Api, message: Message) -> Result<(), Error> {
+        let m = self.messages(&message.from).await;
+        if self.roster.read().await.has_user(message.from.id) {
+            api.send(
+                message
+                    .text_reply(m.already_registered)
+                    .parse_mode(ParseMode::Markdown),
+            )
+            .await?;
+        } else {
+            let mut roster = self.roster.write().await;
+            if message.chat.id() == roster.group_chat {
+                let stored = self
+                    .db
+                    .lock()
+                    .await
+                    .add_user(message.from.id, &message.from.first_name, 1);
+                let reply = match stored {
+                    Ok(()) => {
+                        roster.add_user(message.from.id, message.from.first_name.clone());
+                        m.registered
+                    }
+                    Err(err) => {
+                        eprintln!("failed to register voter {}: {}", message.from.id, err);
+                        m.roster_not_saved
+                    }
+                };
+                drop(roster);
+                api.send(message.text_reply(reply).parse_mode(ParseMode::Markdown))
+                    .await?;
+            } else {
+                drop(roster);
+                api.send(
+                    message
+                        .text_reply(m.no_permission)
+                        .parse_mode(ParseMode::Markdown),
+                )
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    // ADMIN ONLY
+    pub async fn handle_add_admin(&self, api: Api, message: Message) -> Result<(), Error> {
+        let m = self.messages(&message.from).await;
+        if let MessageChat::Private(_) = message.chat {
+            if self.is_admin(message.from.id).await {
+                let token = self.admin_token.lock().await.gen();
+                api.send(
+                    message
+                        .text_reply(&fill(m.admin_token, &[&token]))
+                        .parse_mode(ParseMode::Markdown),
+                )
+                .await?;
+            } else {
+                api.send(
+                    message
+                        .text_reply(m.admin_only)
+                        .parse_mode(ParseMode::Markdown),
+                )
+                .await?;
+            }
+        } else {
+            api.send(
+                message
+                    .text_reply(
+                        m.admin_private_only,
+                    )
+                    .parse_mode(ParseMode::Markdown),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn handle_accept_admin(
+        &self,
+        api: Api,
+        message: Message,
+        command: String,
+    ) -> Result<(), Error> {
+        let m = self.messages(&message.from).await;
+        let splited: Vec<String> = command.split_whitespace().map(|s| s.to_string()).collect();
+        if let Some(parms) = splited.get(1..) {
+            if !(parms.len() == 0 || parms.len() > 1) {
+                if self.is_admin(message.from.id).await {
+                    api.send(
+                        message
+                            .text_reply(m.already_registered)
+                            .parse_mode(ParseMode::Markdown),
+                    )
+                    .await?;
+                } else {
+                    let token = parms[0].clone();
+                    if self.admin_token.lock().await.remove(token) {
+                        let reply = {
+                            let mut roster = self.roster.write().await;
+                            let stored = self
+                                .db
+                                .lock()
+                                .await
+                                .add_admin(message.from.id, &message.from.first_name);
+                            match stored {
+                                Ok(()) => {
+                                    roster.add_admin(
+                                        message.from.id,
+                                        message.from.first_name.clone(),
+                                    );
+                                    self.admins.write().await.insert(message.from.id);
+                                    m.registered
+                                }
+                                Err(err) => {
+                                    eprintln!("failed to add admin {}: {}", message.from.id, err);
+                                    m.roster_not_saved
+                                }
+                            }
+                        };
+                        api.send(message.text_reply(reply).parse_mode(ParseMode::Markdown))
+                            .await?;
+                    } else {
+                        api.send(
+                            message
+                                .text_reply(m.invalid_token)
+                                .parse_mode(ParseMode::Markdown),
+                        )
+                        .await?;
+                    }
+                }
+            } else {
+                self.handle_unknown_command(api.clone(), message.clone())
+                    .await?;
+            }
+        } else {
+            self.handle_unknown_command(api.clone(), message.clone())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Greets a user in a private chat. A `vote_<pollid>` payload, as sent by
+    /// the links in announcements and reminders, skips the greeting and goes
+    /// straight to the ballot of that poll.
+    pub async fn handle_start(
+        &self,
+        api: Api,
+        message: Message,
+        command: String,
+    ) -> Result<(), Error> {
+        let m = self.messages(&message.from).await;
+        if let MessageChat::Private(_) = message.chat {
+            let payload = command.split_whitespace().nth(1).unwrap_or("");
+            if let Some(id) = payload.strip_prefix("vote_") {
+                let poll = self.poll.read().await.clone();
+                let reply = if !self.is_present() || id.parse::<i64>() != Ok(poll.id) {
+                    m.link_expired
+                } else if !self.is_eligible(&poll, message.from.id) {
+                    m.not_eligible
+                } else {
+                    return self.handle_vote(api, message).await;
+                };
+                api.send(message.text_reply(reply).parse_mode(ParseMode::Markdown))
+                    .await?;
+                return Ok(());
+            }
+            api.send(
+                message
+                    .text_reply(&fill(m.welcome, &[&self.name]))
+                    .parse_mode(ParseMode::Markdown),
+            )
+            .await?;
+            if self.is_present() {
+                api.send(
+                    message
+                        .text_reply(m.poll_running)
+                        .parse_mode(ParseMode::Markdown),
+                )
+                .await?;
+                self.handle_vote(api.clone(), message.clone()).await?;
+            }
+        } else {
+            api.send(
+                message
+                    .text_reply(m.private_only)
+                    .parse_mode(ParseMode::Markdown),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    // ADMIN ONLY
+    pub async fn handle_register_chat(&self, api: Api, message: Message) -> Result<(), Error> {
+        let m = self.messages(&message.from).await;
+        if self.is_admin(message.from.id).await {
+            let reply = {
+                let mut roster = self.roster.write().await;
+                match self.db.lock().await.set_group_chat(message.chat.id()) {
+                    Ok(()) => {
+                        roster.set_group_chat(message.chat.id());
+                        m.chat_registered
+                    }
+                    Err(err) => {
+                        eprintln!("failed to register the group chat: {}", err);
+                        m.roster_not_saved
+                    }
+                }
+            };
+            api.send(message.text_reply(reply).parse_mode(ParseMode::Markdown))
+                .await?;
+        } else {
+            api.send(
+                message
+                    .text_reply(m.admin_only)
+                    .parse_mode(ParseMode::Markdown),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    // ADMIN ONLY
+    pub async fn handle_profile(
+        &self,
+        api: Api,
+        message: Message,
+        command: String,
+    ) -> Result<(), Error> {
+        let m = self.messages(&message.from).await;
+        if let MessageChat::Private(_) = message.chat {
+            if self.is_admin(message.from.id).await {
+                if self.is_present() {
+                    let poll = self.poll.read().await.clone();
+                    match command
+                        .split_whitespace()
+                        .nth(1)
+                        .and_then(|t| t.parse::<usize>().ok())
+                    {
+                        Some(idx)
+                            if idx >= 1
+                                && idx <= poll.candidates.len()
+                                && !poll.candidates[idx - 1].is_special() =>
+                        {
+                            let lock = self.dialog_lock(message.from.id).await;
+                            let _guard = lock.lock().await;
+                            self.dialogs.lock().await.insert(
+                                message.from.id,
+                                Dialog::ProfileStatement {
+                                    poll: poll.id,
+                                    candidate: idx - 1,
+                                },
+                            );
+                            api.send(
+                                message
+                                    .text_reply(&fill(
+                                        m.profile_ask_statement,
+                                        &[&idx, &poll.candidates[idx - 1]],
+                                    ))
+                                    .parse_mode(ParseMode::Markdown),
+                            )
+                            .await?;
+                        }
+                        _ => {
+                            api.send(
+                                message
+                                    .text_reply(m.invalid_candidate)
+                                    .parse_mode(ParseMode::Markdown),
+                            )
+                            .await?;
+                        }
+                    }
+                } else {
+                    api.send(message.text_reply(m.no_poll)).await?;
+                }
+            } else {
+                api.send(
+                    message
+                        .text_reply(m.admin_only)
+                        .parse_mode(ParseMode::Markdown),
+                )
+                .await?;
+            }
+        } else {
+            api.send(
+                message
+                    .text_reply(m.admin_private_only)
+                    .parse_mode(ParseMode::Markdown),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Feeds a text message to the sender's open dialog, if there is one.
+    pub async fn handle_dialog(&self, api: Api, message: Message, text: String) -> Result<(), Error> {
+        if let MessageChat::Private(_) = message.chat {
+            let m = self.messages(&message.from).await;
+            let lock = self.dialog_lock(message.from.id).await;
+            let _guard = lock.lock().await;
+            let dialog = self.dialogs.lock().await.remove(&message.from.id);
+            match dialog {
+                Some(Dialog::ProfileStatement { poll, candidate }) => {
+                    self.dialogs.lock().await.insert(
+                        message.from.id,
+                        Dialog::ProfilePhoto {
+                            poll,
+                            candidate,
+                            statement: text,
+                        },
+                    );
+                    api.send(
+                        message
+                            .text_reply(m.profile_ask_photo)
+                            .parse_mode(ParseMode::Markdown),
+                    )
+                    .await?;
+                }
+                Some(Dialog::ProfilePhoto {
+                    poll,
+                    candidate,
+                    statement,
+                }) => {
+                    if text.starts_with("/skip") {
+                        self.save_profile(api, message, poll, candidate, statement, None)
+                            .await?;
+                    } else {
+                        self.dialogs.lock().await.insert(
+                            message.from.id,
+                            Dialog::ProfilePhoto {
+                                poll,
+                                candidate,
+                                statement,
+                            },
+                        );
+                        api.send(
+                            message
+                                .text_reply(m.profile_ask_photo)
+                                .parse_mode(ParseMode::Markdown),
+                        )
+                        .await?;
+                    }
+                }
+                Some(Dialog::Wizard { step, draft }) => {
+                    self.wizard_text(api, message, step, draft, text).await?;
+                }
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Feeds a photo to the sender's open dialog, if it is waiting for one.
+    pub async fn handle_dialog_photo(
+        &self,
+        api: Api,
+        message: Message,
+        file_id: String,
+    ) -> Result<(), Error> {
+        if let MessageChat::Private(_) = message.chat {
+            let lock = self.dialog_lock(message.from.id).await;
+            let _guard = lock.lock().await;
+            let dialog = self.dialogs.lock().await.remove(&message.from.id);
+            match dialog {
+                Some(Dialog::ProfilePhoto {
+                    poll,
+                    candidate,
+                    statement,
+                }) => {
+                    self.save_profile(api, message, poll, candidate, statement, Some(file_id))
+                        .await?;
+                }
+                Some(other) => {
+                    self.dialogs.lock().await.insert(message.from.id, other);
+                }
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn handle_cancel(&self, api: Api, message: Message) -> Result<(), Error> {
+        let m = self.messages(&message.from).await;
+        let lock = self.dialog_lock(message.from.id).await;
+        let _guard = lock.lock().await;
+        if self.dialogs.lock().await.remove(&message.from.id).is_some() {
+            api.send(
+                message
+                    .text_reply(m.dialog_cancelled)
+                    .parse_mode(ParseMode::Markdown),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Stores the profile of `candidate` of the poll with id `poll_id`,
+    /// unless that poll has closed since the dialog started.
+    async fn save_profile(
+        &self,
+        api: Api,
+        message: Message,
+        poll_id: i64,
+        candidate: usize,
+        statement: String,
+        photo: Option<String>,
+    ) -> Result<(), Error> {
+        let m = self.messages(&message.from).await;
+        let reply = {
+            let mut poll = self.poll.write().await;
+            if !self.is_present() {
+                m.no_poll
+            } else if poll.id != poll_id
+                || candidate >= poll.candidates.len()
+                || poll.candidates[candidate].is_special()
+            {
+                m.profile_expired
+            } else {
+                let statement = Some(statement);
+                let stored = self
+                    .db
+                    .lock()
+                    .await
+                    .set_profile(candidate + 1, &statement, &photo);
+                match stored {
+                    Ok(()) => {
+                        poll.candidates[candidate].statement = statement;
+                        poll.candidates[candidate].photo = photo;
+                        m.profile_saved
+                    }
+                    Err(err) => {
+                        eprintln!("failed to store a profile: {}", err);
+                        m.profile_not_saved
+                    }
+                }
+            }
+        };
+        api.send(message.text_reply(reply).parse_mode(ParseMode::Markdown))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn handle_profile_callback(
+        &self,
+        api: Api,
+        callback: CallbackQuery,
+    ) -> Result<(), Error> {
+        let m = self.messages(&callback.from).await;
+        let command = callback.data.clone().unwrap();
+        let target = command
+            .split_whitespace()
+            .nth(1)
+            .and_then(|t| t.parse::<usize>().ok());
+        let poll = self.poll.read().await.clone();
+        match target {
+            Some(idx) if self.is_present() && idx >= 1 && idx <= poll.candidates.len() => {
+                let candidate = &poll.candidates[idx - 1];
+                let statement = match candidate.statement {
+                    Some(ref statement) => escape_markdown(statement),
+                    None => m.no_profile.to_string(),
+                };
+                let text = fill(
+                    m.profile_text,
+                    &[&idx, &escape_markdown(&candidate.name), &statement],
+                );
+                api.send(callback.answer(m.profile_sent)).await?;
+                if let Some(ref photo) = candidate.photo {
+                    api.send(
+                        SendPhoto::new(callback.from.id, InputFileRef::new(photo.clone()))
+                            .caption(&text)
+                            .parse_mode(ParseMode::Markdown),
+                    )
+                    .await?;
+                } else {
+                    api.send(SendMessage::new(callback.from.id, &text).parse_mode(ParseMode::Markdown))
+                        .await?;
+                }
+            }
+            _ => {
+                api.send(callback.answer(m.not_running)).await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn handle_lang(&self, api: Api, message: Message, command: String) -> Result<(), Error> {
+        let splited: Vec<String> = command.split_whitespace().map(|s| s.to_string()).collect();
+        if let Some(lang) = splited.get(1).and_then(|code| Lang::parse(code)) {
+            self.db
+                .lock()
+                .await
+                .set_lang(message.from.id, lang.code())
+                .ok();
+            api.send(
+                message
+                    .text_reply(lang.messages().lang_set)
+                    .parse_mode(ParseMode::Markdown),
+            )
+            .await?;
+        } else {
+            let m = self.messages(&message.from).await;
+            api.send(
+                message
+                    .text_reply(m.lang_usage)
+                    .parse_mode(ParseMode::Markdown),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+use super::Bot;
+use crate::locale::{escape_markdown, fill, Messages};
+use chrono::prelude::*;
+use std::collections::HashSet;
+use tokio::time::{sleep, Duration};
+
+use telegram_bot::prelude::*;
+
+use telegram_bot::types::reply_markup::*;
+use telegram_bot::types::requests::send_message::SendMessage;
+use telegram_bot::types::{ParseMode, UserId};
+
+use telegram_bot::{Api, Error};
+
+/// Pause between direct messages, keeping bulk sends under Telegram's limit
+/// of about 30 messages per second.
+const SEND_INTERVAL: Duration = Duration::from_millis(40);
+
+/// `90` -> "1시간 30분" style, in the language of `m`.
+pub(super) fn format_lead(minutes: i64, m: &Messages) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, min) => fill(m.minutes, &[&min]),
+        (hours, 0) => fill(m.hours, &[&hours]),
+        (hours, min) => format!("{} {}", fill(m.hours, &[&hours]), fill(m.minutes, &[&min])),
+    }
+}
+
+/// Whether a send was refused outright (HTTP 403), as when the user blocked
+/// the bot or never started it. Timeouts and rate limits are not, since the
+/// next send may well go through.
+fn is_forbidden(err: &Error) -> bool {
+    err.to_string().contains("Forbidden")
+}
+
+impl Bot {
+    /// Sends each of `users` a direct message with a button to vote, in
+    /// their own language. Returns the users it could not reach. Those that
+    /// refused the message, because they never started the bot or blocked
+    /// it since, are recorded so later broadcasts can tell them apart.
+    async fn dm_users<F>(&self, api: &Api, users: Vec<UserId>, text: F) -> Vec<UserId>
+    where
+        F: Fn(&Messages) -> String,
+    {
+        let link = self.vote_link(&*self.poll.read().await);
+        let mut failed = vec![];
+        for id in users {
+            let m = self.messages_for(id).await;
+            let mut markup = InlineKeyboardMarkup::new();
+            markup.add_row(vec![InlineKeyboardButton::url(
+                m.go_vote,
+                &link,
+            )]);
+            let sent = api
+                .send(
+                    SendMessage::new(id, text(m))
+                        .reply_markup(ReplyMarkup::InlineKeyboardMarkup(markup))
+                        .parse_mode(ParseMode::Markdown),
+                )
+                .await;
+            if let Err(err) = sent {
+                if is_forbidden(&err) {
+                    self.db.lock().await.mark_blocked(id).ok();
+                }
+                failed.push(id);
+            }
+            sleep(SEND_INTERVAL).await;
+        }
+        failed
+    }
+
+    /// Sends every elector of the running poll a direct message.
+    pub(super) async fn dm_electors<F>(&self, api: &Api, text: F) -> Vec<UserId>
+    where
+        F: Fn(&Messages) -> String,
+    {
+        let electors: Vec<UserId> = self.poll.read().await.electors.keys().copied().collect();
+        self.dm_users(api, electors, text).await
+    }
+
+    /// Tells everyone a poll has opened: a pinned status message in the group and a
+    /// direct message to each elector who has started the bot. Admins then
+    /// get a report of the electors that couldn't be reached. Runs once per
+    /// poll, as soon as it has started.
+    pub async fn announce_poll(&self, api: &Api) -> Result<(), Error> {
+        if !self.is_present() {
+            return Ok(());
+        }
+        let poll = {
+            let mut poll = self.poll.write().await;
+            if poll.announced || !poll.is_started() {
+                return Ok(());
+            }
+            poll.announced = true;
+            self.db.lock().await.set_announced().ok();
+            poll.clone()
+        };
+        self.post_status(api).await?;
+
+        let reachable: HashSet<UserId> = self
+            .db
+            .lock()
+            .await
+            .reachable()
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        let (users, unstarted): (Vec<UserId>, Vec<UserId>) = poll
+            .electors
+            .keys()
+            .copied()
+            .partition(|id| reachable.contains(id));
+        let title = self.title(&poll).clone();
+        let failed = self
+            .dm_users(api, users.clone(), |m| fill(m.poll_opened, &[&title]))
+            .await;
+
+        let admins: Vec<UserId> = self.admins.read().await.iter().copied().collect();
+        for admin in admins {
+            let m = self.messages_for(admin).await;
+            let mut report = fill(
+                m.delivery_report,
+                &[
+                    &title,
+                    &(users.len() - failed.len()),
+                    &failed.len(),
+                    &unstarted.len(),
+                ],
+            );
+            let groups = [
+                (m.delivery_failed, &failed),
+                (m.delivery_unstarted, &unstarted),
+            ];
+            for (header, ids) in groups.iter() {
+                if ids.is_empty() {
+                    continue;
+                }
+                report.push_str(header);
+                for id in ids.iter() {
+                    let name = escape_markdown(&poll.electors[id]);
+                    report.push_str(&fill(m.undelivered_line, &[id, &name]));
+                }
+            }
+            api.send(SendMessage::new(admin, report).parse_mode(ParseMode::Markdown))
+                .await
+                .ok();
+            sleep(SEND_INTERVAL).await;
+        }
+        Ok(())
+    }
+
+    /// Posts the countdown and reminders that have come due. Called
+    /// periodically; every reminder is recorded before it is sent so nobody
+    /// gets the same one twice. Reminders that were due before the poll
+    /// started are skipped.
+    pub async fn send_reminders(&self, api: &Api) -> Result<(), Error> {
+        if !self.is_present() {
+            return Ok(());
+        }
+        let poll = self.poll.read().await.clone();
+        let now = Utc::now().timestamp();
+        if poll.paused || !poll.is_started() || now >= poll.end {
+            return Ok(());
+        }
+        // Only the most urgent reminder that has come due is sent, so one
+        // missed while the bot was down doesn't arrive late next to it.
+        let mut leads = self.config.reminders.clone();
+        leads.sort();
+        let lead = match leads.into_iter().find(|lead| now >= poll.end - lead * 60) {
+            Some(lead) if poll.end - lead * 60 >= poll.start => lead,
+            _ => return Ok(()),
+        };
+        if self.db.lock().await.mark_countdown(lead).unwrap_or(false) {
+            let group_chat = self.roster.read().await.group_chat;
+            let g = self.group_messages().await;
+            let (current, _) = self.db.lock().await.turnout().unwrap_or((0, 0));
+            let text = fill(
+                g.countdown,
+                &[
+                    self.title(&poll),
+                    &format_lead(lead, g),
+                    &current,
+                    &poll.electors.len(),
+                ],
+            );
+            let mut markup = InlineKeyboardMarkup::new();
+            markup.add_row(vec![InlineKeyboardButton::url(
+                g.go_vote,
+                &self.vote_link(&poll),
+            )]);
+            api.send(
+                SendMessage::new(group_chat, text)
+                    .reply_markup(ReplyMarkup::InlineKeyboardMarkup(markup))
+                    .parse_mode(ParseMode::Markdown),
+            )
+            .await?;
+        }
+        let users = {
+            let mut db = self.db.lock().await;
+            let users = db.unreminded(lead).unwrap_or_default();
+            for id in users.iter() {
+                db.mark_reminded(lead, *id).ok();
+            }
+            users
+        };
+        let title = self.title(&poll).clone();
+        self.dm_users(api, users, |m| {
+            fill(m.reminder, &[&title, &format_lead(lead, m)])
+        })
+        .await;
+        Ok(())
+    }
+}
@@ -0,0 +1,337 @@
+use super::Bot;
+use crate::locale::{escape_markdown, fill};
+
+use telegram_bot::prelude::*;
+
+use telegram_bot::types::requests::send_message::SendMessage;
+use telegram_bot::types::{ParseMode, UserId};
+
+use telegram_bot::{Api, Error, Message};
+
+/// Telegram rejects messages longer than 4096 characters.
+const MESSAGE_LIMIT: usize = 4000;
+
+/// Parses one `id,name[,weight]` line of a voter CSV. Surrounding quotes
+/// are dropped. The weight defaults to 1.
+fn parse_voter(line: &str) -> Option<(UserId, String, i64)> {
+    let mut fields = line.splitn(2, ',');
+    let id = fields
+        .next()?
+        .trim()
+        .trim_matches('"')
+        .parse::<i64>()
+        .ok()?;
+    let mut name = fields.next().unwrap_or("");
+    let mut weight = 1;
+    if let Some((rest, last)) = name.rsplit_once(',') {
+        if let Ok(w) = last.trim().trim_matches('"').parse::<i64>() {
+            if w >= 1 {
+                name = rest;
+                weight = w;
+            }
+        }
+    }
+    let name = name.trim().trim_matches('"').to_string();
+    Some((UserId::new(id), name, weight))
+}
+
+fn parse_id(command: &str) -> Option<UserId> {
+    command
+        .split_whitespace()
+        .nth(1)
+        .and_then(|id| id.parse::<i64>().ok())
+        .map(UserId::new)
+}
+
+impl Bot {
+    // ADMIN ONLY
+    pub async fn handle_voters(&self, api: Api, message: Message) -> Result<(), Error> {
+        if !self.check_admin_dm(&api, &message).await? {
+            return Ok(());
+        }
+        let m = self.messages(&message.from).await;
+        let roster = self.roster.read().await.clone();
+        if roster.users.is_empty() {
+            api.send(message.text_reply(m.no_voters)).await?;
+            return Ok(());
+        }
+        let mut pages = vec![fill(m.voters_header, &[&roster.users.len()])];
+        for (id, name) in roster.users.iter() {
+            let weight = match roster.weight(*id) {
+                1 => String::new(),
+                w => fill(m.voter_weight, &[&w]),
+            };
+            let line = if roster.suspended.contains(id) {
+                fill(m.voter_suspended_line, &[id, &escape_markdown(name), &weight])
+            } else {
+                fill(m.voter_line, &[id, &escape_markdown(name), &weight])
+            };
+            if pages.last().unwrap().len() + line.len() > MESSAGE_LIMIT {
+                pages.push(String::new());
+            }
+            pages.last_mut().unwrap().push_str(&line);
+        }
+        for page in pages {
+            api.send(message.text_reply(&page).parse_mode(ParseMode::Markdown))
+                .await?;
+        }
+        Ok(())
+    }
+
+    // ADMIN ONLY
+    pub async fn handle_remove_voter(
+        &self,
+        api: Api,
+        message: Message,
+        command: String,
+    ) -> Result<(), Error> {
+        if !self.check_admin_dm(&api, &message).await? {
+            return Ok(());
+        }
+        let m = self.messages(&message.from).await;
+        let reply = match parse_id(&command) {
+            Some(id) => {
+                let mut roster = self.roster.write().await;
+                if !roster.has_user(id) {
+                    m.voter_not_found.to_string()
+                } else if let Err(err) = self.db.lock().await.remove_user(id) {
+                    eprintln!("failed to remove voter {}: {}", id, err);
+                    m.roster_not_saved.to_string()
+                } else {
+                    roster.remove_user(id);
+                    self.db
+                        .lock()
+                        .await
+                        .audit(message.from.id, "remove_voter", &id.to_string())
+                        .ok();
+                    fill(m.voter_removed, &[&id])
+                }
+            }
+            None => fill(m.voter_usage, &[&"/remove_voter"]),
+        };
+        api.send(message.text_reply(&reply).parse_mode(ParseMode::Markdown))
+            .await?;
+        Ok(())
+    }
+
+    // ADMIN ONLY
+    pub async fn handle_suspend(
+        &self,
+        api: Api,
+        message: Message,
+        command: String,
+        suspended: bool,
+    ) -> Result<(), Error> {
+        if !self.check_admin_dm(&api, &message).await? {
+            return Ok(());
+        }
+        let m = self.messages(&message.from).await;
+        let reply = match parse_id(&command) {
+            Some(id) => {
+                let mut roster = self.roster.write().await;
+                if !roster.has_user(id) {
+                    m.voter_not_found.to_string()
+                } else if let Err(err) = self.db.lock().await.set_suspended(id, suspended) {
+                    eprintln!("failed to suspend voter {}: {}", id, err);
+                    m.roster_not_saved.to_string()
+                } else {
+                    roster.set_suspended(id, suspended);
+                    let action = if suspended { "suspend" } else { "unsuspend" };
+                    self.db
+                        .lock()
+                        .await
+                        .audit(message.from.id, action, &id.to_string())
+                        .ok();
+                    if suspended {
+                        fill(m.voter_suspended, &[&id])
+                    } else {
+                        fill(m.voter_unsuspended, &[&id])
+                    }
+                }
+            }
+            None if suspended => fill(m.voter_usage, &[&"/suspend"]),
+            None => fill(m.voter_usage, &[&"/unsuspend"]),
+        };
+        api.send(message.text_reply(&reply).parse_mode(ParseMode::Markdown))
+            .await?;
+        Ok(())
+    }
+
+    // ADMIN ONLY
+    /// Sets the voting weight of a registered voter. Running polls keep the
+    /// weights they were created with.
+    pub async fn handle_weight(
+        &self,
+        api: Api,
+        message: Message,
+        command: String,
+    ) -> Result<(), Error> {
+        if !self.check_admin_dm(&api, &message).await? {
+            return Ok(());
+        }
+        let m = self.messages(&message.from).await;
+        let weight = command
+            .split_whitespace()
+            .nth(2)
+            .and_then(|w| w.parse::<i64>().ok())
+            .filter(|w| *w >= 1);
+        let reply = match (parse_id(&command), weight) {
+            (Some(id), Some(weight)) => {
+                let mut roster = self.roster.write().await;
+                if !roster.has_user(id) {
+                    m.voter_not_found.to_string()
+                } else if let Err(err) = self.db.lock().await.set_weight(id, weight) {
+                    eprintln!("failed to set the weight of voter {}: {}", id, err);
+                    m.roster_not_saved.to_string()
+                } else {
+                    roster.set_weight(id, weight);
+                    self.db
+                        .lock()
+                        .await
+                        .audit(message.from.id, "weight", &format!("{},{}", id, weight))
+                        .ok();
+                    fill(m.weight_set, &[&id, &weight])
+                }
+            }
+            _ => m.weight_usage.to_string(),
+        };
+        api.send(message.text_reply(&reply).parse_mode(ParseMode::Markdown))
+            .await?;
+        Ok(())
+    }
+
+    // ADMIN ONLY
+    /// Registers every `id,name` line after the command line. Lines that
+    /// don't parse (such as a header) and users that are already registered
+    /// are skipped.
+    pub async fn handle_import_voters(
+        &self,
+        api: Api,
+        message: Message,
+        command: String,
+    ) -> Result<(), Error> {
+        if !self.check_admin_dm(&api, &message).await? {
+            return Ok(());
+        }
+        let m = self.messages(&message.from).await;
+        let lines: Vec<&str> = command
+            .lines()
+            .skip(1)
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        if lines.is_empty() {
+            api.send(message.text_reply(m.import_usage).parse_mode(ParseMode::Markdown))
+                .await?;
+            return Ok(());
+        }
+        let mut imported = vec![];
+        let mut skipped = 0;
+        let mut failed = false;
+        {
+            let mut roster = self.roster.write().await;
+            let mut db = self.db.lock().await;
+            for line in lines {
+                match parse_voter(line) {
+                    Some((id, name, weight)) if !roster.has_user(id) => {
+                        // Voters imported before a failed write stay imported.
+                        if let Err(err) = db.add_user(id, &name, weight) {
+                            eprintln!("failed to import voter {}: {}", id, err);
+                            failed = true;
+                            break;
+                        }
+                        roster.add_user(id, name);
+                        roster.set_weight(id, weight);
+                        imported.push(id.to_string());
+                    }
+                    _ => skipped += 1,
+                }
+            }
+            if !imported.is_empty() {
+                db.audit(message.from.id, "import_voters", &imported.join(","))
+                    .ok();
+            }
+        }
+        let mut reply = fill(m.voters_imported, &[&imported.len(), &skipped]);
+        if failed {
+            reply.push('\n');
+            reply.push_str(m.roster_not_saved);
+        }
+        api.send(message.text_reply(&reply).parse_mode(ParseMode::Markdown))
+            .await?;
+        Ok(())
+    }
+
+    // ADMIN ONLY
+    /// Adds a voter to the running poll's frozen roster. The change is
+    /// written to the audit log and announced in the group chat.
+    pub async fn handle_late_register(
+        &self,
+        api: Api,
+        message: Message,
+        command: String,
+    ) -> Result<(), Error> {
+        if !self.check_admin_dm(&api, &message).await? {
+            return Ok(());
+        }
+        let m = self.messages(&message.from).await;
+        let id = match parse_id(&command) {
+            Some(id) => id,
+            None => {
+                api.send(
+                    message
+                        .text_reply(&fill(m.voter_usage, &[&"/late_register"]))
+                        .parse_mode(ParseMode::Markdown),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+        if !self.is_present() {
+            api.send(message.text_reply(m.no_poll)).await?;
+            return Ok(());
+        }
+        let name = match command.splitn(3, char::is_whitespace).nth(2) {
+            Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+            _ => self
+                .roster
+                .read()
+                .await
+                .users
+                .iter()
+                .find(|(u, _)| *u == id)
+                .map(|(_, name)| name.clone())
+                .unwrap_or_else(|| id.to_string()),
+        };
+        {
+            let mut poll = self.poll.write().await;
+            if poll.electors.contains_key(&id) {
+                drop(poll);
+                api.send(message.text_reply(m.already_elector)).await?;
+                return Ok(());
+            }
+            let weight = self.roster.read().await.weight(id);
+            poll.electors.insert(id, name.clone());
+            if weight != 1 {
+                poll.weights.insert(id, weight);
+            }
+            let mut db = self.db.lock().await;
+            db.add_elector(id, &name, weight).ok();
+            db.audit(message.from.id, "late_register", &format!("{},{}", id, name))
+                .ok();
+        }
+        api.send(
+            message
+                .text_reply(&fill(m.late_registered, &[&id]))
+                .parse_mode(ParseMode::Markdown),
+        )
+        .await?;
+        let group_chat = self.roster.read().await.group_chat;
+        let g = self.group_messages().await;
+        api.send(
+            SendMessage::new(group_chat, fill(g.late_register_notice, &[&name]))
+                .parse_mode(ParseMode::Markdown),
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,894 @@
+use super::{Bot, Kind, Method, PollService};
+use crate::chart::{self, Bar, Shade};
+use crate::locale::{fill, Messages};
+use chrono::prelude::*;
+use chrono_tz::Asia::Seoul;
+use sha3::{Digest, Sha3_256};
+use std::fmt;
+use std::sync::atomic::Ordering;
+
+use telegram_bot::prelude::*;
+
+use telegram_bot::types::reply_markup::*;
+use telegram_bot::types::requests::send_message::SendMessage;
+use telegram_bot::types::{ChatId, ParseMode, UserId};
+
+use telegram_bot::{Api, Error, InputFileUpload, SendPhoto};
+
+/// Telegram's limit on photo captions.
+const CAPTION_LIMIT: usize = 1024;
+
+/// Minimum number of voters for a result to stand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quorum {
+    /// Percentage of the eligible voters, rounded up.
+    Percent(usize),
+    /// Absolute number of voters.
+    Count(usize),
+}
+
+impl Quorum {
+    /// Parses `50%` or `10`.
+    pub fn parse(s: &str) -> Option<Quorum> {
+        match s.strip_suffix('%') {
+            Some(p) => p
+                .parse::<usize>()
+                .ok()
+                .filter(|p| *p <= 100)
+                .map(Quorum::Percent),
+            None => s.parse::<usize>().ok().map(Quorum::Count),
+        }
+    }
+
+    /// Voters needed out of `eligible`.
+    pub fn required(&self, eligible: usize) -> usize {
+        match *self {
+            Quorum::Percent(p) => (eligible * p + 99) / 100,
+            Quorum::Count(n) => n,
+        }
+    }
+}
+
+impl fmt::Display for Quorum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Quorum::Percent(p) => write!(f, "{}%", p),
+            Quorum::Count(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+/// What happens when a poll closes without reaching its quorum.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuorumPolicy {
+    /// Declare the poll void.
+    Void,
+    /// Keep the poll open for this many more minutes, once. If the quorum is
+    /// still not met after that the poll is void.
+    Extend(i64),
+}
+
+impl QuorumPolicy {
+    /// Parses `void` or `extend:[minutes]`.
+    pub fn parse(s: &str) -> Option<QuorumPolicy> {
+        match s {
+            "void" => Some(QuorumPolicy::Void),
+            _ => s
+                .strip_prefix("extend:")
+                .and_then(|m| m.parse::<i64>().ok())
+                .filter(|m| *m > 0)
+                .map(QuorumPolicy::Extend),
+        }
+    }
+}
+
+impl fmt::Display for QuorumPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuorumPolicy::Void => write!(f, "void"),
+            QuorumPolicy::Extend(minutes) => write!(f, "extend:{}", minutes),
+        }
+    }
+}
+
+/// What happens when candidates are tied across the last seat.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TiePolicy {
+    /// Announce the tie and leave the seat undecided.
+    Declare,
+    /// Hold a runoff poll between the tied candidates.
+    Runoff,
+    /// Draw lots with a seed anyone can recompute from the result message.
+    Draw,
+}
+
+impl TiePolicy {
+    pub fn parse(s: &str) -> Option<TiePolicy> {
+        match s {
+            "declare" => Some(TiePolicy::Declare),
+            "runoff" => Some(TiePolicy::Runoff),
+            "draw" => Some(TiePolicy::Draw),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TiePolicy::Declare => "declare",
+            TiePolicy::Runoff => "runoff",
+            TiePolicy::Draw => "draw",
+        }
+    }
+
+    fn name(&self, m: &Messages) -> &'static str {
+        match self {
+            TiePolicy::Declare => m.tie_declare_name,
+            TiePolicy::Runoff => m.tie_runoff_name,
+            TiePolicy::Draw => m.tie_draw_name,
+        }
+    }
+}
+
+/// Share of For votes a referendum needs, counting For and Against only.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Threshold {
+    /// More For than Against.
+    Simple,
+    /// At least two thirds For.
+    TwoThirds,
+    /// At least this percentage For.
+    Percent(usize),
+}
+
+impl Threshold {
+    /// Parses `simple`, `two_thirds` or a percentage such as `60%`.
+    pub fn parse(s: &str) -> Option<Threshold> {
+        match s {
+            "simple" => Some(Threshold::Simple),
+            "two_thirds" => Some(Threshold::TwoThirds),
+            _ => s
+                .trim_end_matches('%')
+                .parse::<usize>()
+                .ok()
+                .filter(|p| *p > 0 && *p <= 100)
+                .map(Threshold::Percent),
+        }
+    }
+
+    pub fn passes(&self, yes: i64, no: i64) -> bool {
+        if yes + no == 0 {
+            return false;
+        }
+        match *self {
+            Threshold::Simple => yes > no,
+            Threshold::TwoThirds => yes * 3 >= (yes + no) * 2,
+            Threshold::Percent(p) => yes * 100 >= (yes + no) * p as i64,
+        }
+    }
+
+    pub fn name(&self, m: &Messages) -> String {
+        match self {
+            Threshold::Simple => m.pass_simple.to_string(),
+            Threshold::TwoThirds => m.pass_two_thirds.to_string(),
+            Threshold::Percent(p) => fill(m.pass_percent, &[p]),
+        }
+    }
+}
+
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Threshold::Simple => write!(f, "simple"),
+            Threshold::TwoThirds => write!(f, "two_thirds"),
+            Threshold::Percent(p) => write!(f, "{}%", p),
+        }
+    }
+}
+
+/// Optional rules of a poll, given as `key=value` words on `/create` or in
+/// the wizard. They are stored in the same form.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rules {
+    pub quorum: Option<Quorum>,
+    pub quorum_policy: QuorumPolicy,
+    /// Number of candidates elected.
+    pub seats: usize,
+    pub tie: TiePolicy,
+    /// Share of the voters, in percent, the leader has to exceed to win a
+    /// single seat outright. Otherwise the top `runoff_top` go to a runoff.
+    pub majority: Option<usize>,
+    pub runoff_top: usize,
+    /// Passing threshold of a referendum.
+    pub pass: Threshold,
+    /// Whether an election offers "none of the above" and Abstain.
+    pub nota: bool,
+    pub abstain: bool,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            quorum: None,
+            quorum_policy: QuorumPolicy::Void,
+            seats: 1,
+            tie: TiePolicy::Declare,
+            majority: None,
+            runoff_top: 2,
+            pass: Threshold::Simple,
+            nota: false,
+            abstain: false,
+        }
+    }
+}
+
+impl Rules {
+    /// Applies one `key=value` word. Returns false if it isn't a valid rule.
+    pub fn set(&mut self, word: &str) -> bool {
+        let mut parts = word.splitn(2, '=');
+        let (key, value) = match (parts.next(), parts.next()) {
+            (Some(key), Some(value)) => (key, value),
+            _ => return false,
+        };
+        match key {
+            "quorum" => match Quorum::parse(value) {
+                Some(quorum) => self.quorum = Some(quorum),
+                None => return false,
+            },
+            "quorum_policy" => match QuorumPolicy::parse(value) {
+                Some(policy) => self.quorum_policy = policy,
+                None => return false,
+            },
+            "seats" => match value.parse::<usize>() {
+                Ok(seats) if seats >= 1 => self.seats = seats,
+                _ => return false,
+            },
+            "tie" => match TiePolicy::parse(value) {
+                Some(tie) => self.tie = tie,
+                None => return false,
+            },
+            "majority" => match value.trim_end_matches('%').parse::<usize>() {
+                Ok(majority) if majority < 100 => self.majority = Some(majority),
+                _ => return false,
+            },
+            "pass" => match Threshold::parse(value) {
+                Some(pass) => self.pass = pass,
+                None => return false,
+            },
+            "nota" | "abstain" => {
+                let on = match value {
+                    "yes" => true,
+                    "no" => false,
+                    _ => return false,
+                };
+                if key == "nota" {
+                    self.nota = on;
+                } else {
+                    self.abstain = on;
+                }
+            }
+            "runoff_top" => match value.parse::<usize>() {
+                Ok(top) if top >= 2 => self.runoff_top = top,
+                _ => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    /// Parses the stored form. Unknown words are ignored.
+    pub fn parse(s: &str) -> Rules {
+        let mut rules = Rules::default();
+        for word in s.split_whitespace() {
+            rules.set(word);
+        }
+        rules
+    }
+
+    /// Summary lines for the poll announcement and the wizard preview.
+    pub fn describe(&self, m: &Messages, eligible: usize, kind: Kind) -> String {
+        let mut lines = String::new();
+        if let Some(quorum) = self.quorum {
+            let required = quorum.required(eligible);
+            lines.push_str(&match self.quorum_policy {
+                QuorumPolicy::Void => fill(m.quorum_void_line, &[&required]),
+                QuorumPolicy::Extend(minutes) => {
+                    fill(m.quorum_extend_line, &[&required, &minutes])
+                }
+            });
+        }
+        if kind == Kind::Referendum {
+            return lines;
+        }
+        if self.seats > 1 {
+            lines.push_str(&fill(m.seats_line, &[&self.seats]));
+        }
+        if let Some(majority) = self.majority {
+            lines.push_str(&fill(m.majority_line, &[&majority, &self.runoff_top]));
+        }
+        lines.push_str(&fill(m.tie_line, &[&self.tie.name(m)]));
+        lines
+    }
+}
+
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut words = vec![];
+        if let Some(quorum) = self.quorum {
+            words.push(format!("quorum={}", quorum));
+            words.push(format!("quorum_policy={}", self.quorum_policy));
+        }
+        words.push(format!("seats={}", self.seats));
+        words.push(format!("tie={}", self.tie.as_str()));
+        if let Some(majority) = self.majority {
+            words.push(format!("majority={}", majority));
+            words.push(format!("runoff_top={}", self.runoff_top));
+        }
+        words.push(format!("pass={}", self.pass));
+        if self.nota {
+            words.push("nota=yes".to_string());
+        }
+        if self.abstain {
+            words.push("abstain=yes".to_string());
+        }
+        write!(f, "{}", words.join(" "))
+    }
+}
+
+/// Final standing of a poll.
+pub struct Outcome {
+    /// `(rank, candidate index, votes)`, best first. Tied candidates share a
+    /// rank.
+    pub ranking: Vec<(usize, usize, i64)>,
+    /// Candidates elected outright.
+    pub winners: Vec<usize>,
+    /// Candidates tied across the last seat and the number of seats left
+    /// for them.
+    pub tie: Option<(Vec<usize>, usize)>,
+}
+
+/// Ranks the entries listed in `counted` by `votes`.
+pub fn tally(votes: &[i64], counted: &[usize], seats: usize) -> Outcome {
+    let mut order = counted.to_vec();
+    order.sort_by(|a, b| votes[*b].cmp(&votes[*a]).then(a.cmp(b)));
+    let ranking = order
+        .iter()
+        .map(|&i| {
+            let rank = order.iter().position(|&j| votes[j] == votes[i]).unwrap() + 1;
+            (rank, i, votes[i])
+        })
+        .collect();
+    let seats = seats.min(order.len());
+    if seats > 0 && seats < order.len() && votes[order[seats - 1]] == votes[order[seats]] {
+        let cut = votes[order[seats - 1]];
+        let winners: Vec<usize> = order.iter().copied().filter(|&i| votes[i] > cut).collect();
+        let tied = order.iter().copied().filter(|&i| votes[i] == cut).collect();
+        let left = seats - winners.len();
+        Outcome {
+            ranking,
+            winners,
+            tie: Some((tied, left)),
+        }
+    } else {
+        Outcome {
+            ranking,
+            winners: order[..seats].to_vec(),
+            tie: None,
+        }
+    }
+}
+
+/// Seed of a tie-breaking draw. The secret is fixed when the poll is
+/// created and only its hash is shown until the result reveals it, so
+/// nobody can steer the draw by changing the poll afterwards; the rest are
+/// values printed in the result message, so anyone can recompute the seed.
+pub fn draw_seed(secret: &str, title: &str, votes: &[i64]) -> String {
+    let votes: Vec<String> = votes.iter().map(|v| v.to_string()).collect();
+    let mut hasher = Sha3_256::new();
+    hasher.update(format!("{}|{}|{}", secret, title, votes.join(",")).as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Orders tied candidates by `SHA3-256(seed|ballot number)`, lowest first.
+pub fn draw(seed: &str, tied: &[usize]) -> Vec<usize> {
+    let mut lots: Vec<(String, usize)> = tied
+        .iter()
+        .map(|&i| {
+            let mut hasher = Sha3_256::new();
+            hasher.update(format!("{}|{}", seed, i + 1).as_bytes());
+            (hex::encode(hasher.finalize()), i)
+        })
+        .collect();
+    lots.sort();
+    lots.into_iter().map(|(_, i)| i).collect()
+}
+
+fn numbers(candidates: &[usize]) -> String {
+    candidates
+        .iter()
+        .map(|i| (i + 1).to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Bot {
+    /// Returns `(voters, required)` if the poll has a quorum.
+    pub(super) async fn quorum_status(&self, poll: &PollService) -> Option<(usize, usize)> {
+        let quorum = poll.rules.quorum?;
+        let (voters, _) = self.db.lock().await.turnout().unwrap_or((0, 0));
+        Some((voters, quorum.required(poll.electors.len())))
+    }
+
+    /// Pushes the end of a poll that is about to close without quorum back,
+    /// if its policy allows. Returns true if the poll was extended.
+    async fn extend_for_quorum(&self) -> bool {
+        let mut poll = self.poll.write().await;
+        let minutes = match poll.rules.quorum_policy {
+            QuorumPolicy::Extend(minutes) if !poll.extended => minutes,
+            _ => return false,
+        };
+        match self.quorum_status(&poll).await {
+            Some((voters, required)) if voters < required => {
+                poll.end += minutes * 60;
+                poll.extended = true;
+                self.db.lock().await.extend(poll.end).ok();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// A runoff of `poll` between `candidates` for `seats` seats. It keeps
+    /// the electors, duration and quorum of the original poll; a tie in it
+    /// is settled by a draw so rounds can't go on forever.
+    fn runoff(
+        &self,
+        m: &Messages,
+        poll: &PollService,
+        candidates: &[usize],
+        seats: usize,
+    ) -> PollService {
+        let mut next = PollService::new(
+            fill(m.runoff_title, &[self.title(poll)]),
+            candidates.iter().map(|&i| poll.candidates[i].clone()).collect(),
+            Utc::now().timestamp(),
+            Utc::now().timestamp() + (poll.end - poll.start).max(60),
+        );
+        next.method = if seats == 1 { Method::Single } else { Method::Approval };
+        next.selections = seats;
+        next.electorate = poll.electorate;
+        next.electors = poll.electors.clone();
+        next.weights = poll.weights.clone();
+        next.parent = Some(poll.id);
+        next.rules.quorum = poll.rules.quorum;
+        next.rules.quorum_policy = poll.rules.quorum_policy;
+        next.rules.seats = seats;
+        next.rules.tie = TiePolicy::Draw;
+        next.rules.nota = poll.rules.nota;
+        next.rules.abstain = poll.rules.abstain;
+        // Electors hear about the runoff from the result announcement.
+        next.announced = true;
+        next
+    }
+
+    /// Builds the result announcement. Returns the entries it declares
+    /// elected (or passed), none if the poll is void or goes to a runoff,
+    /// and the runoff poll to open when the result calls for one.
+    async fn result_message(
+        &self,
+        m: &Messages,
+        poll: &PollService,
+    ) -> (String, Vec<usize>, Option<PollService>) {
+        let counted: Vec<usize> = (0..poll.candidates.len())
+            .filter(|&i| !poll.candidates[i].is_special())
+            .collect();
+        // Ballots are tallied by weight; raw counts are shown next to the
+        // weighted ones when any voter has a weight other than 1.
+        let outcome = tally(&poll.weighted, &counted, poll.rules.seats);
+        let count = |i: usize| {
+            if poll.is_weighted() {
+                fill(m.weighted_count, &[&poll.weighted[i], &poll.votes[i]])
+            } else {
+                fill(m.vote_count, &[&poll.votes[i]])
+            }
+        };
+        let mut result = m.result_header.to_string();
+        match poll.kind {
+            Kind::Election => {
+                for (rank, i, _) in outcome.ranking.iter() {
+                    result.push_str(&fill(
+                        m.result_line,
+                        &[rank, &(i + 1), &poll.candidates[*i], &count(*i)],
+                    ));
+                }
+                // NOTA and Abstain are reported apart from the ranking.
+                for (i, special) in poll.candidates.iter().enumerate() {
+                    if special.is_special() {
+                        result.push_str(&fill(m.option_result_line, &[special, &count(i)]));
+                    }
+                }
+            }
+            Kind::Referendum => {
+                for (i, option) in poll.candidates.iter().enumerate() {
+                    result.push_str(&fill(m.option_result_line, &[option, &count(i)]));
+                }
+            }
+        }
+        if let Some((voters, required)) = self.quorum_status(poll).await {
+            if voters < required {
+                result.push_str(&fill(m.result_void, &[&voters, &required]));
+                return (result, vec![], None);
+            }
+        }
+        if poll.kind == Kind::Referendum {
+            // Options are stored For, Against, Abstain.
+            let (yes, no) = (poll.weighted[0], poll.weighted[1]);
+            let passed = poll.rules.pass.passes(yes, no);
+            result.push_str(&fill(
+                if passed { m.referendum_passed } else { m.referendum_failed },
+                &[&yes, &(yes + no), &poll.rules.pass.name(m)],
+            ));
+            return (result, if passed { vec![0] } else { vec![] }, None);
+        }
+        if let (Some(majority), 1) = (poll.rules.majority, poll.rules.seats) {
+            let voters = self.db.lock().await.weighted_voters().unwrap_or(0);
+            let (_, leader, votes) = outcome.ranking[0];
+            if votes * 100 <= voters * majority as i64 {
+                let last = (poll.rules.runoff_top - 1).min(outcome.ranking.len() - 1);
+                let cut = outcome.ranking[last].2;
+                let top: Vec<usize> = outcome
+                    .ranking
+                    .iter()
+                    .filter(|(_, _, votes)| *votes >= cut)
+                    .map(|(_, i, _)| *i)
+                    .collect();
+                if top.len() > 1 {
+                    result.push_str(&fill(
+                        m.majority_runoff,
+                        &[&(leader + 1), &majority, &numbers(&top)],
+                    ));
+                    return (result, vec![], Some(self.runoff(m, poll, &top, 1)));
+                }
+            }
+        }
+        result.push_str(&fill(m.tie_line, &[&poll.rules.tie.name(m)]));
+        let (tied, left) = match outcome.tie {
+            Some(tie) => tie,
+            None => {
+                result.push_str(m.result_footer);
+                return (result, outcome.winners, None);
+            }
+        };
+        let mut winners = outcome.winners.clone();
+        let mut runoff = None;
+        match poll.rules.tie {
+            TiePolicy::Declare => {
+                result.push_str(&fill(m.tie_declared, &[&numbers(&tied), &left]));
+            }
+            TiePolicy::Runoff => {
+                result.push_str(&fill(m.tie_runoff, &[&numbers(&tied), &left]));
+                runoff = Some(self.runoff(m, poll, &tied, left));
+            }
+            TiePolicy::Draw => {
+                let seed = draw_seed(&poll.draw_secret, self.title(poll), &poll.weighted);
+                let drawn = draw(&seed, &tied);
+                result.push_str(&fill(
+                    m.tie_drawn,
+                    &[
+                        &numbers(&tied),
+                        &poll.draw_secret,
+                        &seed,
+                        &numbers(&drawn[..left]),
+                    ],
+                ));
+                winners.extend_from_slice(&drawn[..left]);
+            }
+        }
+        if !outcome.winners.is_empty() || poll.rules.tie == TiePolicy::Draw {
+            result.push_str(m.result_footer);
+        }
+        (result, winners, runoff)
+    }
+
+    /// Sends a bar chart of the final tally to `chat`, with a caption
+    /// naming the numbered bars. `winners`, as declared by the result
+    /// message, are highlighted, and entries counted apart from the ranking
+    /// are greyed out.
+    async fn send_chart(
+        &self,
+        api: &Api,
+        chat: ChatId,
+        m: &Messages,
+        poll: &PollService,
+        winners: &[usize],
+    ) -> Result<(), Error> {
+        let mut caption = fill(m.chart_caption, &[self.title(poll)]);
+        let mut bars = vec![];
+        for (i, candidate) in poll.candidates.iter().enumerate() {
+            let shade = if winners.contains(&i) {
+                Shade::Winner
+            } else if poll.kind == Kind::Election && candidate.is_special() {
+                Shade::Muted
+            } else {
+                Shade::Normal
+            };
+            bars.push(Bar {
+                label: i + 1,
+                value: poll.weighted[i],
+                shade,
+            });
+            caption.push_str(&fill(m.chart_legend_line, &[&(i + 1), candidate]));
+        }
+        if caption.chars().count() > CAPTION_LIMIT {
+            caption = caption.chars().take(CAPTION_LIMIT - 1).collect();
+            caption.push('…');
+        }
+        let png = match chart::render(&bars) {
+            Ok(png) => png,
+            Err(_) => return Ok(()),
+        };
+        api.send(
+            SendPhoto::new(chat, InputFileUpload::with_data(png, "result.png")).caption(&caption),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Archives and clears the running poll and announces the result in
+    /// `chat`, then opens the runoff if the result calls for one. Returns
+    /// false if no poll was running.
+    ///
+    /// The poll is archived before anything is sent, and failed sends are
+    /// only logged, so Telegram being down can't leave a closed poll behind
+    /// in the database. If archiving fails the poll is paused instead and
+    /// the admins are alerted.
+    pub(super) async fn finish_poll(
+        &self,
+        api: &Api,
+        chat: ChatId,
+        m: &Messages,
+    ) -> Result<bool, Error> {
+        if !self.is_present() {
+            return Ok(false);
+        }
+        self.reconcile(api).await.ok();
+        // The poll lock is held from the count to the archive, so no ballot
+        // or new poll can come in between.
+        let (poll, result, winners, runoff, group_result) = {
+            let mut current = self.poll.write().await;
+            if !self.is_present() {
+                return Ok(false);
+            }
+            let poll = current.clone();
+            let (result, winners, runoff) = self.result_message(m, &poll).await;
+            let group_result = self.result_message(self.group_messages().await, &poll).await.0;
+            let mut db = self.db.lock().await;
+            if let Err(err) = db.archive(&poll) {
+                eprintln!("failed to archive poll {}: {}", poll.id, err);
+                // Paused, it isn't retried every tick but waits for /remove.
+                current.paused = true;
+                db.set_paused(true).ok();
+                drop(db);
+                drop(current);
+                self.alert_close_failed(api, &poll).await;
+                return Ok(true);
+            }
+            self.is_present.store(false, Ordering::SeqCst);
+            (poll, result, winners, runoff, group_result)
+        };
+        if let Err(err) = api.send(SendMessage::new(chat, m.poll_closed)).await {
+            eprintln!("failed to announce the close of poll {}: {}", poll.id, err);
+        }
+        self.close_status(api, group_result).await.ok();
+        if let Err(err) = api
+            .send(SendMessage::new(chat, &result).parse_mode(ParseMode::Markdown))
+            .await
+        {
+            eprintln!("failed to send the result of poll {}: {}", poll.id, err);
+        }
+        self.send_chart(api, chat, m, &poll, &winners).await.ok();
+        if let Some(runoff) = runoff {
+            match self.open_poll(runoff).await {
+                Ok(true) => {
+                    if let Err(err) = self.post_status(api).await {
+                        eprintln!("failed to post the runoff status: {}", err);
+                    }
+                    if chat != self.roster.read().await.group_chat {
+                        let (text, markup) = self.poll_summary(m).await;
+                        api.send(
+                            SendMessage::new(chat, &text)
+                                .reply_markup(ReplyMarkup::InlineKeyboardMarkup(markup))
+                                .parse_mode(ParseMode::Markdown),
+                        )
+                        .await
+                        .ok();
+                    }
+                    let title = self.poll.read().await.title.clone();
+                    self.dm_electors(api, |m| fill(m.runoff_started, &[&title]))
+                        .await;
+                }
+                Ok(false) => {
+                    api.send(SendMessage::new(chat, m.already_running)).await?;
+                }
+                Err(err) => {
+                    eprintln!("failed to store the runoff: {}", err);
+                    api.send(SendMessage::new(chat, m.poll_not_saved)).await?;
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Tells every admin that `poll` could not be archived.
+    async fn alert_close_failed(&self, api: &Api, poll: &PollService) {
+        let admins: Vec<UserId> = self.admins.read().await.iter().copied().collect();
+        for admin in admins {
+            let m = self.messages_for(admin).await;
+            api.send(
+                SendMessage::new(admin, fill(m.close_failed, &[self.title(poll)]))
+                    .parse_mode(ParseMode::Markdown),
+            )
+            .await
+            .ok();
+        }
+    }
+
+    /// Closes the poll once its end has passed, unless it is extended to
+    /// reach quorum instead.
+    pub async fn remove_poll(&self, api: Api) -> Result<(), Error> {
+        if !self.is_present() {
+            return Ok(());
+        }
+        let group_chat = self.roster.read().await.group_chat;
+        let m = self.group_messages().await;
+        if self.extend_for_quorum().await {
+            let (end, required) = {
+                let poll = self.poll.read().await;
+                let required = poll
+                    .rules
+                    .quorum
+                    .map(|q| q.required(poll.electors.len()))
+                    .unwrap_or(0);
+                (poll.end, required)
+            };
+            let end = Seoul.from_utc_datetime(&NaiveDateTime::from_timestamp(end, 0));
+            api.send(
+                SendMessage::new(group_chat, fill(m.quorum_extended, &[&required, &end]))
+                    .parse_mode(ParseMode::Markdown),
+            )
+            .await?;
+            return Ok(());
+        }
+        self.finish_poll(&api, group_chat, m).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tie_for_single_seat() {
+        let outcome = tally(&[3, 3, 1], &[0, 1, 2], 1);
+        assert!(outcome.winners.is_empty());
+        assert_eq!(outcome.tie, Some((vec![0, 1], 1)));
+        assert_eq!(outcome.ranking, vec![(1, 0, 3), (1, 1, 3), (3, 2, 1)]);
+    }
+
+    #[test]
+    fn tie_for_last_of_several_seats() {
+        let outcome = tally(&[5, 3, 3, 1], &[0, 1, 2, 3], 2);
+        assert_eq!(outcome.winners, vec![0]);
+        assert_eq!(outcome.tie, Some((vec![1, 2], 1)));
+    }
+
+    #[test]
+    fn no_tie_when_the_cut_is_clear() {
+        let outcome = tally(&[5, 3, 3, 1], &[0, 1, 2, 3], 3);
+        assert_eq!(outcome.winners, vec![0, 1, 2]);
+        assert_eq!(outcome.tie, None);
+    }
+
+    #[test]
+    fn all_zero_votes_tie_everyone() {
+        let outcome = tally(&[0, 0, 0], &[0, 1, 2], 1);
+        assert!(outcome.winners.is_empty());
+        assert_eq!(outcome.tie, Some((vec![0, 1, 2], 1)));
+    }
+
+    #[test]
+    fn seats_at_least_candidates_elects_all() {
+        let outcome = tally(&[2, 2], &[0, 1], 2);
+        assert_eq!(outcome.winners, vec![0, 1]);
+        assert_eq!(outcome.tie, None);
+        let outcome = tally(&[2, 1], &[0, 1], 5);
+        assert_eq!(outcome.winners, vec![0, 1]);
+        assert_eq!(outcome.tie, None);
+    }
+
+    #[test]
+    fn uncounted_entries_are_left_out() {
+        // Entry 2 stands for NOTA here.
+        let outcome = tally(&[1, 2, 9], &[0, 1], 1);
+        assert_eq!(outcome.winners, vec![1]);
+        assert!(outcome.ranking.iter().all(|(_, i, _)| *i != 2));
+    }
+
+    #[test]
+    fn draw_is_reproducible() {
+        let seed = draw_seed("00ff", "Board", &[3, 3, 1]);
+        assert_eq!(seed, draw_seed("00ff", "Board", &[3, 3, 1]));
+        assert_ne!(seed, draw_seed("00fe", "Board", &[3, 3, 1]));
+        let order = draw(&seed, &[0, 1, 2]);
+        assert_eq!(order, draw(&seed, &[0, 1, 2]));
+        assert_eq!(order, draw(&seed, &[2, 0, 1]));
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(sorted, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn two_thirds_threshold() {
+        assert!(Threshold::TwoThirds.passes(2, 1));
+        assert!(!Threshold::TwoThirds.passes(1, 1));
+        assert!(!Threshold::TwoThirds.passes(199, 101));
+        assert!(Threshold::TwoThirds.passes(200, 100));
+    }
+
+    #[test]
+    fn percent_and_simple_thresholds() {
+        assert!(Threshold::Percent(60).passes(3, 2));
+        assert!(!Threshold::Percent(60).passes(59, 41));
+        assert!(Threshold::Simple.passes(2, 1));
+        assert!(!Threshold::Simple.passes(1, 1));
+    }
+
+    #[test]
+    fn no_votes_never_pass() {
+        assert!(!Threshold::Simple.passes(0, 0));
+        assert!(!Threshold::TwoThirds.passes(0, 0));
+        assert!(!Threshold::Percent(1).passes(0, 0));
+    }
+
+    #[test]
+    fn threshold_round_trip() {
+        for pass in [Threshold::Simple, Threshold::TwoThirds, Threshold::Percent(60)].iter() {
+            assert_eq!(Threshold::parse(&pass.to_string()), Some(*pass));
+        }
+        assert_eq!(Threshold::parse("0%"), None);
+        assert_eq!(Threshold::parse("101%"), None);
+    }
+
+    #[test]
+    fn quorum_percent_rounds_up() {
+        assert_eq!(Quorum::Percent(50).required(5), 3);
+        assert_eq!(Quorum::Percent(50).required(4), 2);
+        assert_eq!(Quorum::Percent(33).required(3), 1);
+        assert_eq!(Quorum::Percent(100).required(7), 7);
+        assert_eq!(Quorum::Percent(0).required(7), 0);
+        assert_eq!(Quorum::Count(10).required(3), 10);
+    }
+
+    #[test]
+    fn quorum_round_trip() {
+        for quorum in [Quorum::Percent(50), Quorum::Count(10)].iter() {
+            assert_eq!(Quorum::parse(&quorum.to_string()), Some(*quorum));
+        }
+        assert_eq!(Quorum::parse("101%"), None);
+    }
+
+    #[test]
+    fn rules_round_trip() {
+        let rules = Rules::default();
+        assert_eq!(Rules::parse(&rules.to_string()), rules);
+
+        let mut rules = Rules::default();
+        for word in "quorum=50% quorum_policy=extend:30 seats=2 tie=draw majority=50 \
+                     runoff_top=3 pass=two_thirds nota=yes abstain=yes"
+            .split_whitespace()
+        {
+            assert!(rules.set(word), "{}", word);
+        }
+        assert_eq!(Rules::parse(&rules.to_string()), rules);
+    }
+}
@@ -0,0 +1,99 @@
+use super::notify::format_lead;
+use super::Bot;
+use crate::locale::fill;
+use chrono::prelude::*;
+
+use telegram_bot::prelude::*;
+
+use telegram_bot::types::reply_markup::*;
+use telegram_bot::types::requests::edit_message_text::EditMessageText;
+use telegram_bot::types::requests::pin_chat_message::PinChatMessage;
+use telegram_bot::types::requests::send_message::SendMessage;
+use telegram_bot::types::{MessageId, ParseMode, ToMessageId};
+
+use telegram_bot::{Api, Error};
+
+/// Minimum seconds between edits of the status message. Telegram limits
+/// how often a group message can be edited, and turnout doesn't need to be
+/// more current than this.
+const STATUS_INTERVAL: i64 = 60;
+
+impl Bot {
+    /// The poll summary with the time left, in the group's language.
+    async fn status_text(&self) -> (String, InlineKeyboardMarkup) {
+        let g = self.group_messages().await;
+        let (mut text, markup) = self.poll_summary(g).await;
+        let left = (self.poll_end().await - Utc::now().timestamp()).max(0) / 60;
+        text.push_str(&fill(g.time_left, &[&format_lead(left, g)]));
+        (text, markup)
+    }
+
+    /// Posts the status message of the running poll to the group and pins
+    /// it. From then on it is kept current by [`Bot::refresh_status`].
+    pub(super) async fn post_status(&self, api: &Api) -> Result<(), Error> {
+        let group_chat = self.roster.read().await.group_chat;
+        let (text, markup) = self.status_text().await;
+        let sent = api
+            .send(
+                SendMessage::new(group_chat, &text)
+                    .reply_markup(ReplyMarkup::InlineKeyboardMarkup(markup))
+                    .parse_mode(ParseMode::Markdown),
+            )
+            .await?;
+        // Pinning needs admin rights in the group; the post stands either way.
+        let message_id = sent.to_message_id();
+        api.send(PinChatMessage::new(group_chat, message_id)).await.ok();
+        let id = i64::from(message_id);
+        self.poll.write().await.status = Some(id);
+        self.db.lock().await.set_status(id).ok();
+        *self.status_edit.lock().await = (Utc::now().timestamp(), text);
+        Ok(())
+    }
+
+    /// Edits the status message to match the running poll. Does nothing if
+    /// it hasn't changed, or if it was edited less than `STATUS_INTERVAL`
+    /// ago unless `force` is set.
+    pub async fn refresh_status(&self, api: &Api, force: bool) -> Result<(), Error> {
+        if !self.is_present() {
+            return Ok(());
+        }
+        let id = match self.poll.read().await.status {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        let now = Utc::now().timestamp();
+        let (text, markup) = self.status_text().await;
+        {
+            let mut last = self.status_edit.lock().await;
+            if last.1 == text || (!force && now - last.0 < STATUS_INTERVAL) {
+                return Ok(());
+            }
+            *last = (now, text.clone());
+        }
+        let group_chat = self.roster.read().await.group_chat;
+        api.send(
+            EditMessageText::new(group_chat, MessageId::new(id), text)
+                .reply_markup(ReplyMarkup::InlineKeyboardMarkup(markup))
+                .parse_mode(ParseMode::Markdown),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Replaces the status message of the poll being closed with its
+    /// results.
+    pub(super) async fn close_status(&self, api: &Api, result: String) -> Result<(), Error> {
+        let id = match self.poll.read().await.status {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        let group_chat = self.roster.read().await.group_chat;
+        api.send(
+            EditMessageText::new(group_chat, MessageId::new(id), result)
+                .parse_mode(ParseMode::Markdown),
+        )
+        .await?;
+        *self.status_edit.lock().await = (0, String::new());
+        Ok(())
+    }
+}
@@ -0,0 +1,428 @@
+use super::{Bot, Candidate, Electorate, Kind, Method, PollService, Rules};
+use crate::dialog::{Dialog, PollDraft, WizardStep};
+use crate::locale::{fill, Messages};
+use chrono::prelude::*;
+use chrono_tz::Asia::Seoul;
+
+use telegram_bot::prelude::*;
+
+use telegram_bot::types::reply_markup::*;
+use telegram_bot::types::requests::send_message::SendMessage;
+use telegram_bot::types::{CallbackQuery, ParseMode, UserId};
+
+use telegram_bot::{Api, Error, Message};
+
+const TIME_FORMAT: &'static str = "%Y-%m-%d %H:%M";
+
+/// Parses a Seoul local time such as `2021-03-01 18:00`.
+pub(super) fn parse_time(text: &str) -> Option<i64> {
+    Seoul
+        .datetime_from_str(text, TIME_FORMAT)
+        .ok()
+        .map(|t| t.timestamp())
+}
+
+pub(super) fn format_time(timestamp: i64) -> String {
+    Seoul
+        .from_utc_datetime(&NaiveDateTime::from_timestamp(timestamp, 0))
+        .to_string()
+}
+
+fn electorate_name(electorate: Electorate, m: &Messages) -> &'static str {
+    match electorate {
+        Electorate::Users => m.electorate_users,
+        Electorate::Admins => m.electorate_admins,
+    }
+}
+
+impl Bot {
+    /// Starts the creation wizard for an admin in a private chat.
+    pub(super) async fn start_wizard(&self, api: Api, message: Message) -> Result<(), Error> {
+        if !self.check_admin_dm(&api, &message).await? {
+            return Ok(());
+        }
+        let m = self.messages(&message.from).await;
+        let lock = self.dialog_lock(message.from.id).await;
+        let _guard = lock.lock().await;
+        let draft = PollDraft::new();
+        self.wizard_prompt(&api, message.from.id, m, WizardStep::Title, &draft)
+            .await?;
+        self.dialogs.lock().await.insert(
+            message.from.id,
+            Dialog::Wizard {
+                step: WizardStep::Title,
+                draft,
+            },
+        );
+        Ok(())
+    }
+
+    /// Asks the question for `step`.
+    async fn wizard_prompt(
+        &self,
+        api: &Api,
+        user: UserId,
+        m: &Messages,
+        step: WizardStep,
+        draft: &PollDraft,
+    ) -> Result<(), Error> {
+        let mut markup = InlineKeyboardMarkup::new();
+        let text = match step {
+            WizardStep::Title => m.wizard_title.to_string(),
+            WizardStep::Kind => {
+                markup.add_row(vec![InlineKeyboardButton::callback(
+                    m.kind_election_button,
+                    "/wizard kind election",
+                )]);
+                markup.add_row(vec![InlineKeyboardButton::callback(
+                    m.kind_referendum_button,
+                    "/wizard kind referendum",
+                )]);
+                m.wizard_kind.to_string()
+            }
+            WizardStep::Candidates => m.wizard_candidates.to_string(),
+            WizardStep::Method => {
+                markup.add_row(vec![InlineKeyboardButton::callback(
+                    m.method_approval_button,
+                    "/wizard method approval",
+                )]);
+                markup.add_row(vec![InlineKeyboardButton::callback(
+                    m.method_single_button,
+                    "/wizard method single",
+                )]);
+                m.wizard_method.to_string()
+            }
+            WizardStep::Selections => fill(m.wizard_selections, &[&draft.candidates.len()]),
+            WizardStep::Start => m.wizard_start.to_string(),
+            WizardStep::Rules => m.wizard_rules.to_string(),
+            WizardStep::End => m.wizard_end.to_string(),
+            WizardStep::Electorate => {
+                markup.add_row(vec![InlineKeyboardButton::callback(
+                    m.electorate_users,
+                    "/wizard electorate users",
+                )]);
+                markup.add_row(vec![InlineKeyboardButton::callback(
+                    m.electorate_admins,
+                    "/wizard electorate admins",
+                )]);
+                m.wizard_electorate.to_string()
+            }
+            WizardStep::Confirm => {
+                markup.add_row(vec![
+                    InlineKeyboardButton::callback(m.wizard_confirm_button, "/wizard confirm"),
+                    InlineKeyboardButton::callback(m.wizard_cancel_button, "/wizard cancel"),
+                ]);
+                self.wizard_preview(m, draft).await
+            }
+        };
+        api.send(
+            SendMessage::new(user, &text)
+                .reply_markup(ReplyMarkup::InlineKeyboardMarkup(markup))
+                .parse_mode(ParseMode::Markdown),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn wizard_preview(&self, m: &Messages, draft: &PollDraft) -> String {
+        let mut preview = m.wizard_preview.to_string();
+        match draft.kind {
+            Kind::Election => {
+                preview.push_str(&fill(
+                    m.candidate_count,
+                    &[&draft.title, &draft.candidates.len()],
+                ));
+                for (i, name) in draft.candidates.iter().enumerate() {
+                    preview.push_str(&fill(m.candidate_line, &[&(i + 1), name]));
+                }
+                preview.push_str(&match draft.method {
+                    Method::Approval => fill(m.method_approval, &[&draft.selections]),
+                    Method::Single => m.method_single.to_string(),
+                });
+            }
+            Kind::Referendum => {
+                preview.push_str(&fill(m.referendum_header, &[&draft.title]));
+                preview.push_str(&fill(m.pass_line, &[&draft.rules.pass.name(m)]));
+            }
+        }
+        preview.push_str(&fill(m.start_time, &[&format_time(draft.start)]));
+        preview.push_str(&fill(m.end_time, &[&format_time(draft.end)]));
+        preview.push_str(&fill(
+            m.electorate_line,
+            &[&electorate_name(draft.electorate, m)],
+        ));
+        let eligible = {
+            let roster = self.roster.read().await;
+            match draft.electorate {
+                Electorate::Users => roster.active_users().len(),
+                Electorate::Admins => roster.admins.len(),
+            }
+        };
+        preview.push_str(&draft.rules.describe(m, eligible, draft.kind));
+        preview
+    }
+
+    /// Feeds a text answer to the wizard. Buttons are handled by
+    /// [`Bot::handle_wizard_callback`].
+    pub(super) async fn wizard_text(
+        &self,
+        api: Api,
+        message: Message,
+        step: WizardStep,
+        mut draft: PollDraft,
+        text: String,
+    ) -> Result<(), Error> {
+        let m = self.messages(&message.from).await;
+        let user = message.from.id;
+        let text = text.trim().to_string();
+        let now = Utc::now().timestamp();
+        let mut reply = None;
+        let is_done = (step == WizardStep::Candidates && text.starts_with("/done"))
+            || (step == WizardStep::Rules && text.starts_with("/skip"));
+        let next = match step {
+            // Other commands are never taken as answers.
+            _ if text.starts_with('/') && !is_done => step,
+            WizardStep::Title => {
+                draft.title = text;
+                WizardStep::Kind
+            }
+            WizardStep::Candidates => {
+                if text.starts_with("/done") {
+                    if draft.candidates.is_empty() {
+                        reply = Some(m.need_candidate.to_string());
+                        WizardStep::Candidates
+                    } else {
+                        WizardStep::Method
+                    }
+                } else {
+                    draft.candidates.push(text);
+                    reply = Some(fill(m.wizard_candidate_added, &[&draft.candidates.len()]));
+                    WizardStep::Candidates
+                }
+            }
+            WizardStep::Selections => match text.parse::<usize>() {
+                Ok(n) if n >= 1 && n <= draft.candidates.len() => {
+                    draft.selections = n;
+                    WizardStep::Start
+                }
+                _ => {
+                    reply = Some(fill(m.wizard_selections, &[&draft.candidates.len()]));
+                    WizardStep::Selections
+                }
+            },
+            WizardStep::Start => {
+                let start = if text == "now" || text == "지금" {
+                    Some(now)
+                } else {
+                    parse_time(&text).filter(|start| *start >= now - 60)
+                };
+                match start {
+                    Some(start) => {
+                        draft.start = start;
+                        WizardStep::End
+                    }
+                    None => {
+                        reply = Some(m.invalid_time.to_string());
+                        WizardStep::Start
+                    }
+                }
+            }
+            WizardStep::End => {
+                let end = match text.parse::<i64>() {
+                    Ok(minutes) => Some(draft.start + minutes * 60),
+                    Err(_) => parse_time(&text),
+                };
+                match end.filter(|end| *end >= draft.start + 60 && *end > now) {
+                    Some(end) => {
+                        draft.end = end;
+                        WizardStep::Electorate
+                    }
+                    None => {
+                        reply = Some(m.invalid_time.to_string());
+                        WizardStep::End
+                    }
+                }
+            }
+            WizardStep::Rules => {
+                let mut rules = Rules::default();
+                let invalid = text
+                    .split_whitespace()
+                    .filter(|word| !word.starts_with("/skip"))
+                    .find(|word| !rules.set(word));
+                match invalid {
+                    Some(word) => {
+                        reply = Some(fill(m.invalid_rule, &[&word]));
+                        WizardStep::Rules
+                    }
+                    None => {
+                        draft.rules = rules;
+                        WizardStep::Confirm
+                    }
+                }
+            }
+            // These steps are answered with buttons.
+            WizardStep::Kind | WizardStep::Method | WizardStep::Electorate | WizardStep::Confirm => {
+                step
+            }
+        };
+        if let Some(reply) = reply {
+            api.send(message.text_reply(&reply).parse_mode(ParseMode::Markdown))
+                .await?;
+        } else {
+            self.wizard_prompt(&api, user, m, next, &draft).await?;
+        }
+        self.dialogs
+            .lock()
+            .await
+            .insert(user, Dialog::Wizard { step: next, draft });
+        Ok(())
+    }
+
+    pub async fn handle_wizard_callback(
+        &self,
+        api: Api,
+        callback: CallbackQuery,
+    ) -> Result<(), Error> {
+        let m = self.messages(&callback.from).await;
+        let user = callback.from.id;
+        let command = callback.data.clone().unwrap();
+        let args: Vec<&str> = command.split_whitespace().collect();
+        let lock = self.dialog_lock(user).await;
+        let _guard = lock.lock().await;
+        let dialog = self.dialogs.lock().await.remove(&user);
+        let (step, mut draft) = match dialog {
+            Some(Dialog::Wizard { step, draft }) => (step, draft),
+            Some(other) => {
+                self.dialogs.lock().await.insert(user, other);
+                api.send(callback.answer(m.wizard_expired)).await?;
+                return Ok(());
+            }
+            None => {
+                api.send(callback.answer(m.wizard_expired)).await?;
+                return Ok(());
+            }
+        };
+        let next = match (step, args.get(1).copied(), args.get(2).copied()) {
+            (_, Some("cancel"), _) => {
+                api.send(callback.answer(m.dialog_cancelled)).await?;
+                api.send(SendMessage::new(user, m.dialog_cancelled)).await?;
+                return Ok(());
+            }
+            (WizardStep::Kind, Some("kind"), Some(kind)) => match Kind::parse(kind) {
+                Some(Kind::Election) => {
+                    draft.kind = Kind::Election;
+                    WizardStep::Candidates
+                }
+                Some(Kind::Referendum) => {
+                    draft.kind = Kind::Referendum;
+                    WizardStep::Start
+                }
+                None => step,
+            },
+            (WizardStep::Method, Some("method"), Some(method)) => match Method::parse(method) {
+                Some(Method::Approval) => {
+                    draft.method = Method::Approval;
+                    WizardStep::Selections
+                }
+                Some(Method::Single) => {
+                    draft.method = Method::Single;
+                    draft.selections = 1;
+                    WizardStep::Start
+                }
+                None => step,
+            },
+            (WizardStep::Electorate, Some("electorate"), Some(electorate)) => {
+                match Electorate::parse(electorate) {
+                    Some(electorate) => {
+                        draft.electorate = electorate;
+                        WizardStep::Rules
+                    }
+                    None => step,
+                }
+            }
+            (WizardStep::Confirm, Some("confirm"), _) => {
+                api.send(callback.answer(m.wizard_confirm_button)).await?;
+                return self.wizard_finish(api, user, m, draft).await;
+            }
+            _ => {
+                self.dialogs
+                    .lock()
+                    .await
+                    .insert(user, Dialog::Wizard { step, draft });
+                api.send(callback.answer(m.wizard_expired)).await?;
+                return Ok(());
+            }
+        };
+        api.send(callback.answer("")).await?;
+        if next != step {
+            self.wizard_prompt(&api, user, m, next, &draft).await?;
+        }
+        self.dialogs
+            .lock()
+            .await
+            .insert(user, Dialog::Wizard { step: next, draft });
+        Ok(())
+    }
+
+    /// Creates the poll described by a confirmed draft.
+    async fn wizard_finish(
+        &self,
+        api: Api,
+        user: UserId,
+        m: &Messages,
+        draft: PollDraft,
+    ) -> Result<(), Error> {
+        // The user may have lost admin rights while the wizard was open.
+        if !self.is_admin(user).await {
+            api.send(SendMessage::new(user, m.admin_only).parse_mode(ParseMode::Markdown))
+                .await?;
+            return Ok(());
+        }
+        if draft.end <= Utc::now().timestamp() {
+            api.send(SendMessage::new(user, m.invalid_time)).await?;
+            return Ok(());
+        }
+        let mut poll = match draft.kind {
+            Kind::Election => {
+                let mut poll = PollService::new(
+                    draft.title,
+                    draft.candidates.into_iter().map(Candidate::new).collect(),
+                    draft.start,
+                    draft.end,
+                );
+                poll.method = draft.method;
+                poll.selections = draft.selections;
+                poll
+            }
+            Kind::Referendum => PollService::referendum(
+                draft.title,
+                draft.start,
+                draft.end,
+                self.group_messages().await,
+            ),
+        };
+        poll.electorate = draft.electorate;
+        poll.rules = draft.rules;
+        match self.open_poll(poll).await {
+            Ok(true) => {
+                self.audit_open(user).await;
+                let (text, markup) = self.poll_summary(m).await;
+                api.send(
+                    SendMessage::new(user, &text)
+                        .reply_markup(ReplyMarkup::InlineKeyboardMarkup(markup))
+                        .parse_mode(ParseMode::Markdown),
+                )
+                .await?;
+                self.announce_poll(&api).await?;
+            }
+            Ok(false) => {
+                api.send(SendMessage::new(user, m.already_running)).await?;
+            }
+            Err(err) => {
+                eprintln!("failed to store the new poll: {}", err);
+                api.send(SendMessage::new(user, m.poll_not_saved)).await?;
+            }
+        }
+        Ok(())
+    }
+}
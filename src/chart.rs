@@ -0,0 +1,130 @@
+//! PNG bar charts of poll results, drawn pixel by pixel so no fonts or
+//! external services are needed. Bars are labelled with numbers only; the
+//! caption sent along with the image says what each number stands for.
+
+const WIDTH: usize = 640;
+const HEIGHT: usize = 400;
+const MARGIN: usize = 40;
+/// Size in pixels of one dot of the digit font.
+const DOT: usize = 4;
+const GLYPH_WIDTH: usize = 3 * DOT;
+const GLYPH_HEIGHT: usize = 5 * DOT;
+
+const BACKGROUND: [u8; 3] = [255, 255, 255];
+const INK: [u8; 3] = [48, 48, 48];
+
+/// 3x5 dot digits, one row per byte with the leftmost dot in bit 2.
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shade {
+    Normal,
+    Winner,
+    /// Entries reported apart from the ranking, such as NOTA.
+    Muted,
+}
+
+impl Shade {
+    fn color(self) -> [u8; 3] {
+        match self {
+            Shade::Normal => [66, 133, 244],
+            Shade::Winner => [244, 160, 0],
+            Shade::Muted => [176, 176, 176],
+        }
+    }
+}
+
+pub struct Bar {
+    /// Number printed under the bar.
+    pub label: usize,
+    pub value: i64,
+    pub shade: Shade,
+}
+
+struct Canvas {
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new() -> Self {
+        Canvas {
+            pixels: BACKGROUND.repeat(WIDTH * HEIGHT),
+        }
+    }
+
+    fn rect(&mut self, x: usize, y: usize, w: usize, h: usize, color: [u8; 3]) {
+        for row in y..(y + h).min(HEIGHT) {
+            for col in x..(x + w).min(WIDTH) {
+                let at = (row * WIDTH + col) * 3;
+                self.pixels[at..at + 3].copy_from_slice(&color);
+            }
+        }
+    }
+
+    /// Draws the digits of `n` centered on `center`, with their top at `y`.
+    fn number(&mut self, center: usize, y: usize, n: i64) {
+        let text = n.to_string();
+        let width = text.len() * (GLYPH_WIDTH + DOT) - DOT;
+        let mut x = center.saturating_sub(width / 2);
+        for c in text.chars() {
+            if let Some(d) = c.to_digit(10) {
+                for (row, bits) in DIGITS[d as usize].iter().enumerate() {
+                    for col in 0..3 {
+                        if bits & (0b100 >> col) != 0 {
+                            self.rect(x + col * DOT, y + row * DOT, DOT, DOT, INK);
+                        }
+                    }
+                }
+            } else {
+                // Minus sign.
+                self.rect(x, y + 2 * DOT, GLYPH_WIDTH, DOT, INK);
+            }
+            x += GLYPH_WIDTH + DOT;
+        }
+    }
+}
+
+/// Renders `bars` left to right as a PNG image, each topped by its value.
+pub fn render(bars: &[Bar]) -> Result<Vec<u8>, png::EncodingError> {
+    let mut canvas = Canvas::new();
+    let baseline = HEIGHT - MARGIN - GLYPH_HEIGHT;
+    let top = MARGIN + GLYPH_HEIGHT + DOT;
+    let slot = (WIDTH - 2 * MARGIN) / bars.len().max(1);
+    let max = bars.iter().map(|b| b.value).max().unwrap_or(0).max(1);
+    for (i, bar) in bars.iter().enumerate() {
+        let center = MARGIN + slot * i + slot / 2;
+        let width = (slot * 2 / 3).max(DOT);
+        let height = (bar.value.max(0) as usize) * (baseline - top) / max as usize;
+        canvas.rect(
+            center - width / 2,
+            baseline - height,
+            width,
+            height,
+            bar.shade.color(),
+        );
+        canvas.number(center, baseline - height - GLYPH_HEIGHT - DOT, bar.value);
+        canvas.number(center, baseline + DOT * 2, bar.label as i64);
+    }
+    canvas.rect(MARGIN, baseline, WIDTH - 2 * MARGIN, DOT / 2, INK);
+
+    let mut data = vec![];
+    {
+        let mut encoder = png::Encoder::new(&mut data, WIDTH as u32, HEIGHT as u32);
+        encoder.set_color(png::ColorType::RGB);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&canvas.pixels)?;
+    }
+    Ok(data)
+}
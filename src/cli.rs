@@ -0,0 +1,164 @@
+//! Offline subcommands that read `bot.db` directly, for checking an
+//! election without the bot or Telegram. The database is opened read-only
+//! and never migrated, so they are safe to run next to the bot.
+
+use crate::bot::to_csv;
+use crate::constants::*;
+use crate::middlewares::db::{audit_hash, DbService};
+use chrono::prelude::*;
+use chrono_tz::Asia::Seoul;
+use std::path::Path;
+
+const USAGE: &str = "Usage: f6tgbot <command>
+
+Commands:
+  show                  Print the running poll
+  tally                 Check the stored ballots against the totals in stats
+  verify                Check the hash chain of the audit trail
+  export [poll] [csv]   Print the record of a closed poll as JSON or CSV
+
+Without a command the bot is started.";
+
+fn time(t: i64) -> String {
+    Seoul.timestamp(t, 0).format("%Y-%m-%d %H:%M").to_string()
+}
+
+/// Runs the subcommand in `args` and returns the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    if !Path::new(DB_PATH).exists() {
+        eprintln!("{} not found", DB_PATH);
+        return 2;
+    }
+    let mut db = match DbService::open_read_only() {
+        Ok(db) => db,
+        Err(err) => {
+            eprintln!("failed to open {}: {}", DB_PATH, err);
+            return 2;
+        }
+    };
+    let res = match args[0].as_str() {
+        "show" => show(&mut db),
+        "tally" => tally(&mut db),
+        "verify" => verify(&mut db),
+        "export" => export(&mut db, &args[1..]),
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+    match res {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(err) => {
+            eprintln!("{}", err);
+            2
+        }
+    }
+}
+
+fn show(db: &mut DbService) -> Result<bool, sqlite::Error> {
+    if !db.is_present()? {
+        println!("No poll is running.");
+        return Ok(true);
+    }
+    let poll = db.load()?;
+    let (current, ever) = db.turnout()?;
+    println!("Poll {}: {}", poll.id, poll.title);
+    if let Some(parent) = poll.parent {
+        println!("Runoff of poll {}", parent);
+    }
+    println!("Kind: {}", poll.kind.as_str());
+    println!("Method: {} (up to {})", poll.method.as_str(), poll.limit());
+    println!("Rules: {}", poll.rules);
+    println!("Start: {}", time(poll.start));
+    println!(
+        "End: {}{}",
+        time(poll.end),
+        if poll.paused { " (paused)" } else { "" }
+    );
+    println!(
+        "Electors: {} ({}), {} holding a ballot, {} voted at some point",
+        poll.electors.len(),
+        poll.electorate.as_str(),
+        current,
+        ever
+    );
+    for (i, candidate) in poll.candidates.iter().enumerate() {
+        println!(
+            "{:>3}. {} [{}]: {} votes, {} weighted",
+            i + 1,
+            candidate.name,
+            candidate.choice.as_str(),
+            poll.votes[i],
+            poll.weighted[i]
+        );
+    }
+    Ok(true)
+}
+
+/// Ballots are encrypted with a key only the voter holds, so which
+/// candidate each one is for can't be recovered here. What can be checked
+/// is that the ballots on file add up to the totals, both raw and
+/// weighted, and that nobody holds more ballots than the method allows.
+fn tally(db: &mut DbService) -> Result<bool, sqlite::Error> {
+    if !db.is_present()? {
+        println!("No poll is running.");
+        return Ok(true);
+    }
+    let poll = db.load()?;
+    let ballots: i64 = db.ballot_counts()?.iter().map(|(_, count, _)| count).sum();
+    let votes: i64 = poll.votes.iter().sum();
+    println!("Ballots on file: {}, votes in stats: {}", ballots, votes);
+    let mismatches = db.reconcile(poll.limit())?;
+    for mismatch in mismatches.iter() {
+        println!("MISMATCH: {}", mismatch);
+    }
+    let ok = mismatches.is_empty();
+    println!("{}", if ok { "OK" } else { "FAILED" });
+    Ok(ok)
+}
+
+fn verify(db: &mut DbService) -> Result<bool, sqlite::Error> {
+    let log = db.audit_log()?;
+    let mut prev = String::new();
+    for (id, time, admin, action, detail, hash) in log.iter() {
+        if audit_hash(&prev, *time, *admin, action, detail) != *hash {
+            println!(
+                "BROKEN: audit entry {} ({}) doesn't match its hash",
+                id, action
+            );
+            println!("Entries from {} on can't be trusted.", id);
+            return Ok(false);
+        }
+        prev = hash.clone();
+    }
+    println!("OK: {} audit entries, chain intact", log.len());
+    Ok(true)
+}
+
+fn export(db: &mut DbService, args: &[String]) -> Result<bool, sqlite::Error> {
+    let csv = args.iter().any(|a| a == "csv");
+    let id = match args.iter().find(|a| *a != "csv") {
+        Some(arg) => match arg.parse::<i64>() {
+            Ok(id) => Some(id),
+            Err(_) => {
+                eprintln!("{}", USAGE);
+                return Ok(false);
+            }
+        },
+        None => db.last_closed()?,
+    };
+    let record = match id {
+        Some(id) => db.record(id)?,
+        None => None,
+    };
+    match record {
+        Some(record) if csv => print!("{}", to_csv(&record)),
+        Some(record) => println!("{}", serde_json::to_string_pretty(&record).unwrap()),
+        None => {
+            eprintln!("No closed poll to export.");
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
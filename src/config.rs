@@ -1,46 +1,121 @@
-use serde::{Deserialize, Serialize};
-use std::fs::{File, OpenOptions};
+use crate::constants::NONCE_LEN;
+use crate::locale::Lang;
+use serde::Deserialize;
+use std::fmt;
+use std::fs::File;
 use std::io::prelude::*;
 use telegram_bot::types::{ChatId, UserId};
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+/// Why `Bot.toml` couldn't be loaded.
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(String, std::io::Error),
+    Parse(String, toml::de::Error),
+    /// The file parsed but a setting is unusable.
+    Invalid(String, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(file, err) => write!(f, "{}: {}", file, err),
+            ConfigError::Parse(file, err) => write!(f, "{}: invalid TOML: {}", file, err),
+            ConfigError::Invalid(file, msg) => write!(f, "{}: {}", file, msg),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Deserialize, Clone, Debug)]
 pub struct Security {
     pub nonce: String,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+/// Branding overrides. Anything left out is taken from the bot's `getMe` info.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Branding {
+    pub name: Option<String>,
+    /// Link to a private chat with the bot. Vote links add a `start`
+    /// parameter to it, so it should be the bot's `t.me` address.
+    pub url: Option<String>,
+}
+
+/// Settings read from `Bot.toml`. The bot never writes the file, so it can
+/// be mounted read-only; everything that changes at runtime is kept in the
+/// database.
+#[derive(Deserialize, Clone, Debug)]
 pub struct Config {
     pub token: String,
+    /// Admins that always exist, so there is someone to hand out admin
+    /// tokens on a fresh database.
+    #[serde(default)]
     pub admins: Vec<(UserId, String)>,
+    /// Roster of older versions that kept it here. Imported into the
+    /// database once, then ignored.
+    #[serde(default)]
     pub users: Vec<(UserId, String)>,
-    pub group_chat: ChatId,
+    #[serde(default)]
+    pub suspended: Vec<UserId>,
+    #[serde(default)]
+    pub weights: Vec<(UserId, i64)>,
+    #[serde(default)]
+    pub group_chat: Option<ChatId>,
+    /// Language of messages sent to the group chat (`ko` or `en`).
+    pub language: Option<String>,
     pub security: Security,
+    pub bot: Option<Branding>,
+    /// Minutes before the end of a poll at which voters who haven't voted
+    /// yet are reminded.
+    #[serde(default = "default_reminders")]
+    pub reminders: Vec<i64>,
+}
+
+fn default_reminders() -> Vec<i64> {
+    vec![24 * 60, 60]
 }
 
 impl Config {
-    pub fn open(filename: &str) -> Result<Self, std::io::Error> {
-        let mut f = File::open(filename)?;
+    /// Reads and validates the config at `filename`.
+    pub fn open(filename: &str) -> Result<Self, ConfigError> {
+        let io = |err| ConfigError::Io(filename.to_string(), err);
         let mut s = String::new();
-        f.read_to_string(&mut s)?;
-        Ok(toml::from_str(&s).unwrap())
-    }
-
-    pub fn add_admin(&mut self, id: UserId, name: String) {
-        self.admins.push((id, name));
-    }
-
-    pub fn add_user(&mut self, id: UserId, name: String) {
-        self.users.push((id, name));
-    }
-
-    pub fn set_group_chat(&mut self, id: ChatId) {
-        self.group_chat = id;
+        File::open(filename)
+            .and_then(|mut f| f.read_to_string(&mut s))
+            .map_err(io)?;
+        let config: Config =
+            toml::from_str(&s).map_err(|err| ConfigError::Parse(filename.to_string(), err))?;
+        config
+            .validate()
+            .map_err(|msg| ConfigError::Invalid(filename.to_string(), msg))?;
+        Ok(config)
     }
 
-    pub fn sync(&self, filename: &str) -> Result<(), std::io::Error> {
-        let mut f = OpenOptions::new().write(true).open(filename)?;
-        f.write_all(toml::to_string(self).unwrap().as_bytes())?;
-        f.sync_all()?;
+    /// Checks the settings serde can't.
+    fn validate(&self) -> Result<(), String> {
+        if self.token.trim().is_empty() {
+            return Err("`token` is empty".to_string());
+        }
+        if self.security.nonce.len() > NONCE_LEN {
+            return Err(format!(
+                "`security.nonce` is longer than {} bytes",
+                NONCE_LEN
+            ));
+        }
+        if let Some(ref language) = self.language {
+            if Lang::parse(language).is_none() {
+                return Err(format!(
+                    "`language` must be `ko` or `en`, not `{}`",
+                    language
+                ));
+            }
+        }
+        if let Some(lead) = self.reminders.iter().find(|lead| **lead <= 0) {
+            return Err(format!(
+                "`reminders` must be positive minutes, not {}",
+                lead
+            ));
+        }
         Ok(())
     }
 }
@@ -0,0 +1,64 @@
+use crate::bot::{Electorate, Kind, Method, Rules};
+
+/// Poll settings collected by the creation wizard.
+#[derive(Clone, Debug)]
+pub struct PollDraft {
+    pub title: String,
+    pub kind: Kind,
+    pub candidates: Vec<String>,
+    pub method: Method,
+    pub selections: usize,
+    pub start: i64,
+    pub end: i64,
+    pub electorate: Electorate,
+    pub rules: Rules,
+}
+
+impl PollDraft {
+    pub fn new() -> Self {
+        Self {
+            title: String::new(),
+            kind: Kind::Election,
+            candidates: vec![],
+            method: Method::Approval,
+            selections: 1,
+            start: 0,
+            end: 0,
+            electorate: Electorate::Users,
+            rules: Rules::default(),
+        }
+    }
+}
+
+/// Question the creation wizard is waiting on an answer for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WizardStep {
+    Title,
+    Kind,
+    Candidates,
+    Method,
+    Selections,
+    Start,
+    End,
+    Electorate,
+    Rules,
+    Confirm,
+}
+
+/// Step of a multi-message conversation an admin is having with the bot in
+/// a private chat. The next plain message (or photo) from that admin is fed
+/// to the step they are on.
+#[derive(Clone, Debug)]
+pub enum Dialog {
+    /// Waiting for the platform statement of a candidate (0-based index)
+    /// of the poll with id `poll`.
+    ProfileStatement { poll: i64, candidate: usize },
+    /// Waiting for the candidate photo, or `/skip`.
+    ProfilePhoto {
+        poll: i64,
+        candidate: usize,
+        statement: String,
+    },
+    /// Creating a poll through `/create` without arguments.
+    Wizard { step: WizardStep, draft: PollDraft },
+}
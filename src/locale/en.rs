@@ -0,0 +1,192 @@
+use super::Messages;
+
+pub const MESSAGES: Messages = Messages {
+    help: "*{} Help*
+`/start`: Start the bot.
+`/about`: Show information about the bot.
+`/help`: Show this help.
+`/lang [ko|en]`: Set the bot language.
+`/poll`: Show the current poll.
+`/vote`: Cast your vote.
+`/admin_help`: Show the admin help.
+`/accept`: Register yourself as a voter.
+`/accept_admin [token]`: Register yourself as an admin with a valid token.",
+    admin_help: "*{} Admin Help*
+`/create`: Create a new poll step by step.
+`/create [title] [candidate names...] [duration (minutes)]`: Start a new poll. Write spaces in the title and names as `/S/`. Rules such as `quorum=50%` can be added as `key=value`.
+`/create [title] [duration (minutes)] type=referendum`: Start a For/Against/Abstain referendum. Set the passing threshold with `pass=simple|two_thirds|[%]`.
+`/profile [number]`: Add a statement and photo for a candidate.
+`/pause`, `/resume`: Pause or resume the running poll.
+`/set_end [minutes|YYYY-MM-DD HH:MM]`: Change when the poll ends.
+`/remove`: Close the current poll.
+`/voters`: List registered voters.
+`/remove_voter [user ID]`: Remove a voter.
+`/suspend [user ID]`, `/unsuspend [user ID]`: Suspend or restore a voter's voting rights.
+`/import_voters`: Register voters in bulk from `user ID,name[,weight]` CSV lines after the command.
+`/weight [user ID] [weight]`: Set a voter's voting weight. It applies to polls created afterwards.
+`/late_register [user ID] [name]`: Add a voter to the running poll. The change is audited.
+`/export [poll number]`: Get the record of a closed poll as JSON and CSV files. Defaults to the most recent poll.
+`/add_admin`: Generate a token for registering an admin.
+`/register_chat`: Register this chat as the poll chat.",
+    about: "*{}*\nVersion: {}",
+    welcome: "Welcome to {}!",
+    unknown_command: "Sorry, that command is unknown.\nSend `/help` to see the help.",
+    admin_only: "Sorry, this command is for admins only.",
+    admin_private_only: "Sorry, admin commands can only be used in a private chat.",
+    private_only: "This command can only be used in a private chat.",
+    no_permission: "Sorry, you don't have permission.",
+    already_registered: "Sorry, you are already registered.",
+    registered: "You have been registered.",
+    no_voters: "There are no registered voters.",
+    voters_header: "*Registered voters ({})*\n",
+    voter_line: "`{}` {}{}\n",
+    voter_suspended_line: "`{}` {}{} (suspended)\n",
+    voter_weight: " (weight {})",
+    voter_usage: "Usage: `{} [user ID]`",
+    voter_not_found: "Sorry, that user is not registered.",
+    roster_not_saved: "Sorry, the change could not be saved. Please try again later.",
+    voter_removed: "User `{}` has been removed.",
+    voter_suspended: "Voting rights of user `{}` have been suspended.",
+    late_registered: "User `{}` has been added to the running poll's voters.",
+    late_register_notice: "An admin added `{}` to this poll's voters.",
+    already_elector: "That user is already a voter in this poll.",
+    voter_unsuspended: "Voting rights of user `{}` have been restored.",
+    import_usage: "Put one `user ID,name` per line after the command.\nExample:\n/import\\_voters\n12345678,Jane Doe",
+    voters_imported: "Registered {} voters. Skipped lines: {}",
+    weight_usage: "Usage: `/weight [user ID] [weight]` (weight is a whole number of at least 1)",
+    weight_set: "Set the weight of user `{}` to {}. Running polls are not affected.",
+    chat_registered: "This chat has been registered as the poll chat.",
+    admin_token: "A token has been generated. Only give it to the intended person. `/accept_admin {}`",
+    invalid_token: "Sorry, the token you entered is not valid.",
+    lang_set: "Language set to English.",
+    lang_usage: "Current language: English\nUse `/lang ko` or `/lang en` to change it.",
+    no_poll: "Sorry, there doesn't seem to be an open poll.",
+    not_started: "Sorry, the poll hasn't started yet.",
+    not_running: "Sorry, no poll is running right now.",
+    paused: "Sorry, voting is paused.",
+    already_paused: "The poll is already paused.",
+    not_paused: "The poll is not paused.",
+    poll_paused: "An admin has paused the poll. Voting is unavailable until it resumes.",
+    poll_resumed: "Voting has resumed.",
+    set_end_usage: "Usage: `/set_end [minutes from now]` or `/set_end YYYY-MM-DD HH:MM`\nThe end must be in the future and at least 1 minute after the start.",
+    end_changed: "The poll now ends at {}.",
+    poll_running: "A poll is currently running.",
+    status_above: "⬆️ The poll status is kept up to date in this message.",
+    already_running: "Sorry, a poll seems to be running already.",
+    poll_not_saved: "Sorry, the poll could not be saved. Please try again later.",
+    need_candidate: "Sorry, you must enter at least one candidate.",
+    min_duration: "Sorry, a poll must run for at least `1 minute`.",
+    poll_closed: "The poll has closed.",
+    quorum_extended: "The poll did not reach its quorum of {} voters and has been extended until {}. Please vote if you haven't yet!",
+    countdown: "*{}* closes in {}. {} of {} voters have voted so far.",
+    reminder: "You haven't voted in *{}* yet. It closes in {}.",
+    hours: "{} h",
+    minutes: "{} min",
+    candidate_count: "*{}*\nCandidates: {}\n",
+    paused_line: "*Paused*\n",
+    runoff_line: "*Runoff of poll #{}.*\n",
+    candidate_line: "*No. {}*: {}\n",
+    referendum_header: "*{}*\nReferendum\n",
+    option_line: "{}. {}\n",
+    option_for: "For",
+    option_against: "Against",
+    option_abstain: "Abstain",
+    option_nota: "None of the above",
+    pass_line: "*Method*: choose one; passes with {}\n",
+    pass_simple: "more For than Against, not counting abstentions",
+    pass_two_thirds: "at least two thirds For, not counting abstentions",
+    pass_percent: "at least {}% For, not counting abstentions",
+    method_approval: "*Method*: choose up to {}\n",
+    method_single: "*Method*: choose one\n",
+    electorate_line: "*Voters*: {}\n",
+    electorate_users: "Registered voters",
+    electorate_admins: "Admins",
+    how_to_vote: "How to vote: press a button.\n",
+    start_time: "*Start*: {}\n",
+    end_time: "*End*: {}\n",
+    time_left: "*Time left*: {}\n",
+    turnout: "*Turnout*: {}/{} voters ({}%)\n",
+    participation: "*Voted at least once*: {}/{} voters ({}%)\n",
+    quorum_void_line: "*Quorum*: {} voters (void if not met)\n",
+    quorum_extend_line: "*Quorum*: {} voters (extended by {} minutes if not met)\n",
+    seats_line: "*Seats*: {}\n",
+    majority_line: "*Majority*: more than {}% of voters (otherwise a runoff between the top {})\n",
+    tie_line: "*Ties*: {}\n",
+    draw_commitment_line: "*Draw secret hash*: `{}`\n",
+    tie_declare_name: "declared as a tie",
+    tie_runoff_name: "runoff between the tied candidates",
+    tie_draw_name: "draw with a public seed",
+    go_vote: "Go vote",
+    vote_button: "No. {}",
+    check_button: "Show my votes",
+    profile_button: "Profile",
+    clear_button: "Vote again",
+    not_eligible: "Sorry, only registered voters can vote.",
+    link_expired: "The poll behind this link has already closed. Use /poll to see what is running now.",
+    check_line: "Vote {}: {} ",
+    no_ballot: "You haven't voted for anyone yet.",
+    cleared: "Your votes have been cleared.",
+    retracted: "Your vote has been withdrawn.",
+    voted: "Thank you for voting. Your vote for {} has been recorded.",
+    ballot_failed: "Your ballot could not be saved. Please try again shortly.",
+    invalid_candidate: "Sorry, please enter a valid candidate number.",
+    profile_ask_statement: "Send the platform statement of candidate No. {} {}.\nSend `/cancel` to stop.",
+    profile_ask_photo: "Send a photo of the candidate, or `/skip` if there is none.",
+    profile_saved: "The candidate profile has been saved.",
+    profile_expired: "The poll changed while you were editing, so the profile was not saved.",
+    profile_not_saved: "Sorry, the profile could not be saved. Please try again later.",
+    profile_text: "*No. {} {}*\n{}",
+    profile_sent: "The profile has been sent to your private chat.",
+    no_profile: "No profile has been added.",
+    wizard_title: "Let's create a new poll. Send the poll title.\nYou can stop at any time with `/cancel`.",
+    wizard_kind: "What kind of poll is this?",
+    kind_election_button: "Election",
+    kind_referendum_button: "Referendum",
+    wizard_candidates: "Send the candidate names, one per message.\nSend `/done` when you are finished.",
+    wizard_candidate_added: "Candidate {} added. Send the next one or `/done`.",
+    wizard_method: "Choose the voting method.",
+    method_approval_button: "Choose several",
+    method_single_button: "Choose one",
+    wizard_selections: "How many candidates may each voter choose? (1-{})",
+    wizard_start: "Send the start time as `YYYY-MM-DD HH:MM` (Korea time).\nSend `now` to start right away.",
+    wizard_end: "Send the end time as `YYYY-MM-DD HH:MM`, or the duration from the start in minutes.",
+    invalid_time: "Sorry, that time is not valid. A poll must run for at least `1 minute` and can't end in the past.",
+    wizard_electorate: "Choose who may vote.",
+    wizard_rules: "Enter extra rules as space-separated `key=value` words, or /skip.\n`quorum=50%` or `quorum=10`: quorum, as a share of voters or a head count\n`quorum_policy=void` or `quorum_policy=extend:[minutes]`: void the poll or extend it once when quorum is not met\n`seats=[count]`: number of winners (default 1)\n`tie=declare|runoff|draw`: on a tie, declare it, hold a runoff or draw lots with a public seed\n`majority=[%]`, `runoff_top=[count]`: hold a runoff between the top candidates when the leader gets no more than that share of voters (single seat only)\n`pass=simple|two_thirds|[%]`: passing threshold of a referendum, not counting abstentions\n`nota=yes`, `abstain=yes`: offer \"None of the above\" and Abstain in an election",
+    invalid_rule: "Unknown or invalid rule: `{}`",
+    wizard_preview: "*New poll preview*\n",
+    wizard_confirm_button: "Confirm",
+    wizard_cancel_button: "Cancel",
+    wizard_expired: "Sorry, this button has expired.",
+    dialog_cancelled: "Cancelled.",
+    stale_ballot: "Sorry, please vote from the message you voted with before.",
+    result_header: "*Results*\n",
+    result_line: "#{}: No. {} {} ({})\n",
+    vote_count: "{} votes",
+    weighted_count: "{} weighted, {} ballots",
+    result_footer: "Congratulations to the winners!",
+    chart_caption: "{} results\n",
+    chart_legend_line: "{}. {}\n",
+    result_void: "Only {} voted, short of the quorum of {}. This poll is void.",
+    tie_declared: "Candidates No. {} are tied. The remaining {} seat(s) are undecided.\n",
+    tie_runoff: "Candidates No. {} are tied. A runoff will decide the remaining {} seat(s).\n",
+    tie_drawn: "Candidates No. {} are tied and lots were drawn.\nSecret: `{}` (its SHA3-256 was published when the poll opened)\nSeed: `{}` (SHA3-256 of secret|title|vote counts)\nElected by lowest SHA3-256(seed|number): No. {}\n",
+    majority_runoff: "The leader, No. {}, did not get more than {}% of voters. Candidates No. {} go to a runoff.\n",
+    runoff_started: "The *{}* runoff has started. Please vote now!",
+    poll_opened: "*{}* is now open. Tap the button below to vote!",
+    delivery_report: "*{}* opening notice: {} sent, {} failed, {} never started the bot\n",
+    delivery_failed: "\nFailed (e.g. blocked the bot):\n",
+    delivery_unstarted: "\nElectors who never started the bot:\n",
+    undelivered_line: "`{}` {}\n",
+    runoff_title: "{} runoff",
+    option_result_line: "{}: {}\n",
+    referendum_passed: "*Passed* with {} For out of {} For and Against. (Threshold: {})",
+    referendum_failed: "*Failed* with {} For out of {} For and Against. (Threshold: {})",
+    export_usage: "Usage: `/export [poll number]`",
+    export_running: "A running poll can be exported once it has closed.",
+    export_not_found: "There is no closed poll with that number.",
+    export_caption: "Record of poll {}: {}",
+    tally_mismatch: "⚠️ The counts of *{}* don't match the stored ballots:\n",
+    close_failed: "⚠️ *{}* could not be archived, so it was left open and paused. Nothing was deleted. Use /remove to try closing it again.",
+    mismatch_line: "`{}`\n",
+};
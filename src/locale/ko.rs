@@ -0,0 +1,192 @@
+use super::Messages;
+
+pub const MESSAGES: Messages = Messages {
+    help: "*{} 도움말*
+`/start`: 봇을 시작합니다.
+`/about`: 봇의 정보를 보여줍니다.
+`/help`: 도움말을 표시합니다.
+`/lang [ko|en]`: 봇의 언어를 설정합니다.
+`/poll`: 현재 진행중인 투표를 보여줍니다.
+`/vote`: 투표를 진행합니다.
+`/admin_help`: 관리자 전용 도움말을 표시합니다.
+`/accept`: 자신을 투표 가능한 유저로 등록합니다. 
+`/accept_admin [토큰]`: 유효한 토큰을 통해서 자신을 관리자로 등록합니다.",
+    admin_help: "*{} 관리자 도움말*
+`/create`: 대화형으로 새로운 투표를 만듭니다.
+`/create [제목] [후보 이름...] [진행할 시간(분)]`: 새로운 투표를 시작합니다. 제목과 이름의 공백은 `/S/` 로 입력합니다. `quorum=50%` 처럼 `키=값` 규칙을 덧붙일 수 있습니다.
+`/create [제목] [진행할 시간(분)] type=referendum`: 찬성/반대/기권 찬반 투표를 시작합니다. `pass=simple|two_thirds|[%]` 로 가결 기준을 정합니다.
+`/profile [기호]`: 후보의 소개글과 사진을 등록합니다.
+`/pause`, `/resume`: 진행 중인 투표를 일시 정지하거나 다시 시작합니다.
+`/set_end [분|YYYY-MM-DD HH:MM]`: 투표 종료 시각을 바꿉니다.
+`/remove`: 현재 진행중인 투표를 종료합니다.
+`/voters`: 등록된 유저 목록을 보여줍니다.
+`/remove_voter [유저 ID]`: 유저의 등록을 삭제합니다.
+`/suspend [유저 ID]`, `/unsuspend [유저 ID]`: 유저의 투표 권한을 정지하거나 복구합니다.
+`/import_voters`: 다음 줄부터 `유저ID,이름[,가중치]` CSV로 유저를 일괄 등록합니다.
+`/weight [유저 ID] [가중치]`: 유저의 투표 가중치를 정합니다. 새로 만드는 투표부터 적용됩니다.
+`/late_register [유저 ID] [이름]`: 진행 중인 투표에 유권자를 추가합니다. 기록이 남습니다.
+`/export [투표 번호]`: 종료된 투표의 기록을 JSON과 CSV 파일로 받습니다. 번호를 생략하면 가장 최근 투표입니다.
+`/add_admin`: 관리자를 등록하기 위한 토큰을 생성합니다.
+`/register_chat`: 투표 관리 챗을 등록합니다.",
+    about: "*{}*\n버전: {}",
+    welcome: "{}에 오신걸 환영합니다!",
+    unknown_command: "죄송합니다. 알 수 없는 명령어가 입력되었습니다.\n도움말을 보시려면 `/help` 명령어를 입력해주세요.",
+    admin_only: "죄송합니다. 관리자 전용 명령어 입니다.",
+    admin_private_only: "죄송합니다. 관리자 전용 명령어는 개인 챗에서만 이용할 수 있습니다.",
+    private_only: "이 명령어는 개인 대화에서만 사용하실 수 있습니다.",
+    no_permission: "죄송합니다. 권한이 없습니다.",
+    already_registered: "죄송합니다. 이미 등록되어있습니다.",
+    registered: "정상적으로 등록이 완료되었습니다.",
+    no_voters: "등록된 유저가 없습니다.",
+    voters_header: "*등록된 유저 ({}명)*\n",
+    voter_line: "`{}` {}{}\n",
+    voter_suspended_line: "`{}` {}{} (정지됨)\n",
+    voter_weight: " (가중치 {})",
+    voter_usage: "사용법: `{} [유저 ID]`",
+    voter_not_found: "죄송합니다. 등록된 유저가 아닙니다.",
+    roster_not_saved: "변경 사항을 저장하지 못했습니다. 잠시 후 다시 시도해주세요.",
+    voter_removed: "유저 `{}` 의 등록이 삭제되었습니다.",
+    voter_suspended: "유저 `{}` 의 투표 권한이 정지되었습니다.",
+    late_registered: "유저 `{}` 을(를) 진행 중인 투표의 유권자로 추가했습니다.",
+    late_register_notice: "관리자가 `{}` 을(를) 이번 투표의 유권자로 추가 등록했습니다.",
+    already_elector: "이미 이번 투표의 유권자입니다.",
+    voter_unsuspended: "유저 `{}` 의 투표 권한이 복구되었습니다.",
+    import_usage: "명령어 다음 줄부터 한 줄에 한 명씩 `유저ID,이름` 형식으로 입력해주세요.\n예:\n/import\\_voters\n12345678,홍길동",
+    voters_imported: "{}명을 등록했습니다. 건너뛴 줄: {}",
+    weight_usage: "사용법: `/weight [유저 ID] [가중치]` (가중치는 1 이상의 정수)",
+    weight_set: "유저 `{}` 의 가중치를 {}(으)로 정했습니다. 진행 중인 투표에는 적용되지 않습니다.",
+    chat_registered: "정상적으로 투표 관리 챗이 등록되었습니다.",
+    admin_token: "토큰이 생성되었습니다. 꼭 대상자에게만 지급하십시오. `/accept_admin {}`",
+    invalid_token: "죄송합니다. 입력하신 토큰이 유효하지 않습니다.",
+    lang_set: "언어가 한국어로 설정되었습니다.",
+    lang_usage: "현재 언어: 한국어\n`/lang ko` 또는 `/lang en` 으로 언어를 바꿀 수 있습니다.",
+    no_poll: "죄송합니다. 아직 투표가 열려있지 않은 것 같습니다.",
+    not_started: "죄송합니다. 아직 투표가 시작되지 않았습니다.",
+    not_running: "죄송합니다. 현재 투표가 진행중이 아닙니다.",
+    paused: "죄송합니다. 투표가 일시 정지되었습니다.",
+    already_paused: "투표가 이미 일시 정지되어 있습니다.",
+    not_paused: "투표가 일시 정지되어 있지 않습니다.",
+    poll_paused: "관리자가 투표를 일시 정지했습니다. 다시 시작될 때까지 투표할 수 없습니다.",
+    poll_resumed: "투표가 다시 시작되었습니다.",
+    set_end_usage: "사용법: `/set_end [지금부터 분]` 또는 `/set_end YYYY-MM-DD HH:MM`\n종료 시각은 미래여야 하고 시작 후 1분 이상이어야 합니다.",
+    end_changed: "투표 종료 시각이 {}(으)로 변경되었습니다.",
+    poll_running: "현재 투표가 진행중입니다.",
+    status_above: "⬆️ 투표 현황은 이 메시지에서 계속 갱신됩니다.",
+    already_running: "죄송합니다. 투표가 이미 진행 중인 것 같습니다.",
+    poll_not_saved: "투표를 저장하지 못했습니다. 잠시 후 다시 시도해주세요.",
+    need_candidate: "죄송합니다. 후보자는 한명 이상 입력하셔야 합니다.",
+    min_duration: "죄송합니다. 투표는 `1분` 이상 진행되어야 합니다.",
+    poll_closed: "투표가 종료되었습니다.",
+    quorum_extended: "정족수({}명)에 미달하여 투표가 {}까지 연장되었습니다. 아직 투표하지 않으셨다면 참여해주세요!",
+    countdown: "*{}* 투표 종료까지 {} 남았습니다. 현재 {}/{}명이 투표했습니다.",
+    reminder: "아직 *{}* 투표에 참여하지 않으셨습니다. 투표 종료까지 {} 남았습니다.",
+    hours: "{}시간",
+    minutes: "{}분",
+    candidate_count: "*{}*\n총 후보 수: {}\n",
+    paused_line: "*일시 정지됨*\n",
+    runoff_line: "*{}번 투표의 결선 투표입니다.*\n",
+    candidate_line: "*기호 {}번*: {}\n",
+    referendum_header: "*{}*\n찬반 투표\n",
+    option_line: "{}. {}\n",
+    option_for: "찬성",
+    option_against: "반대",
+    option_abstain: "기권",
+    option_nota: "지지 후보 없음",
+    pass_line: "*방식*: 하나만 선택, 가결 기준: {}\n",
+    pass_simple: "기권을 제외한 과반 찬성",
+    pass_two_thirds: "기권을 제외한 3분의 2 이상 찬성",
+    pass_percent: "기권을 제외한 {}% 이상 찬성",
+    method_approval: "*방식*: 최대 {}명까지 선택\n",
+    method_single: "*방식*: 1명 선택\n",
+    electorate_line: "*투표 대상*: {}\n",
+    electorate_users: "등록된 유저",
+    electorate_admins: "관리자",
+    how_to_vote: "투표 방법: 버튼을 클릭하세요.\n",
+    start_time: "*시작*: {}\n",
+    end_time: "*종료*: {}\n",
+    time_left: "*남은 시간*: {}\n",
+    turnout: "*투표율*: {}/{}명 ({}%)\n",
+    participation: "*한 번 이상 투표*: {}/{}명 ({}%)\n",
+    quorum_void_line: "*정족수*: {}명 (미달 시 무효)\n",
+    quorum_extend_line: "*정족수*: {}명 (미달 시 {}분 연장)\n",
+    seats_line: "*당선 인원*: {}명\n",
+    majority_line: "*당선 기준*: 투표자의 {}% 초과 득표 (미달 시 상위 {}명 결선 투표)\n",
+    tie_line: "*동점 처리*: {}\n",
+    draw_commitment_line: "*추첨 비밀값 해시*: `{}`\n",
+    tie_declare_name: "동점 선언",
+    tie_runoff_name: "동점 후보 결선 투표",
+    tie_draw_name: "공개 시드 추첨",
+    go_vote: "투표하러 가기",
+    vote_button: "기호 {}번",
+    check_button: "투표한 후보 보기",
+    profile_button: "후보 소개",
+    clear_button: "다시 투표하기",
+    not_eligible: "죄송합니다. 투표는 허용된 유저만 할 수 있습니다.",
+    link_expired: "이 링크의 투표는 이미 종료되었습니다. /poll 로 진행 중인 투표를 확인하세요.",
+    check_line: "{}번 후보: {} ",
+    no_ballot: "현재 투표한 후보가 없습니다.",
+    cleared: "투표가 성공적으로 초기화 되었습니다.",
+    retracted: "정상적으로 투표가 취소되었습니다.",
+    voted: "투표해주셔서 감사합니다. {} 후보에게 정상적으로 투표가 완료되었습니다.",
+    ballot_failed: "투표를 저장하지 못했습니다. 잠시 후 다시 시도해주세요.",
+    invalid_candidate: "죄송합니다. 올바른 기호를 입력해주세요.",
+    profile_ask_statement: "기호 {}번 {} 후보의 소개글을 보내주세요.\n취소하려면 `/cancel` 을 입력하세요.",
+    profile_ask_photo: "후보 사진을 보내주세요. 사진이 없으면 `/skip` 을 입력하세요.",
+    profile_saved: "후보 소개가 저장되었습니다.",
+    profile_expired: "편집하는 동안 투표가 바뀌어 프로필을 저장하지 않았습니다.",
+    profile_not_saved: "프로필을 저장하지 못했습니다. 잠시 후 다시 시도해주세요.",
+    profile_text: "*기호 {}번 {}*\n{}",
+    profile_sent: "후보 소개를 개인 대화로 보내드렸습니다.",
+    no_profile: "등록된 소개가 없습니다.",
+    wizard_title: "새 투표를 만듭니다. 투표 제목을 보내주세요.\n언제든 `/cancel` 로 취소할 수 있습니다.",
+    wizard_kind: "어떤 투표인가요?",
+    kind_election_button: "후보 선출",
+    kind_referendum_button: "찬반 투표",
+    wizard_candidates: "후보 이름을 한 메시지에 한 명씩 보내주세요.\n모두 입력했으면 `/done` 을 입력하세요.",
+    wizard_candidate_added: "{}번째 후보가 추가되었습니다. 다음 후보를 보내거나 `/done` 을 입력하세요.",
+    wizard_method: "투표 방식을 골라주세요.",
+    method_approval_button: "여러 명 선택",
+    method_single_button: "한 명 선택",
+    wizard_selections: "한 사람이 최대 몇 명까지 선택할 수 있나요? (1~{})",
+    wizard_start: "투표 시작 시각을 `YYYY-MM-DD HH:MM` 형식(한국 시간)으로 보내주세요.\n바로 시작하려면 `now` 를 입력하세요.",
+    wizard_end: "투표 종료 시각을 `YYYY-MM-DD HH:MM` 형식으로 보내거나, 시작부터 진행할 시간(분)을 숫자로 보내주세요.",
+    invalid_time: "죄송합니다. 올바른 시각이 아닙니다. 투표는 `1분` 이상 진행되어야 하고 과거에 끝날 수 없습니다.",
+    wizard_electorate: "투표할 수 있는 대상을 골라주세요.",
+    wizard_rules: "추가 규칙을 `키=값` 형식으로 띄어 써서 입력하거나 /skip 을 입력하세요.\n`quorum=50%` 또는 `quorum=10`: 정족수 (유권자 비율 또는 인원)\n`quorum_policy=void` 또는 `quorum_policy=extend:[분]`: 정족수 미달 시 무효 처리 또는 한 번 연장\n`seats=[인원]`: 당선 인원 (기본 1)\n`tie=declare|runoff|draw`: 동점 시 동점 선언, 결선 투표 또는 공개 시드 추첨\n`majority=[%]`, `runoff_top=[인원]`: 1위가 투표자의 해당 비율을 넘지 못하면 상위 후보끼리 결선 투표 (당선 1명일 때)\n`pass=simple|two_thirds|[%]`: 찬반 투표의 가결 기준 (기권 제외)\n`nota=yes`, `abstain=yes`: 후보 투표에 \"지지 후보 없음\"과 기권 선택지 추가",
+    invalid_rule: "알 수 없거나 잘못된 규칙입니다: `{}`",
+    wizard_preview: "*새 투표 미리보기*\n",
+    wizard_confirm_button: "확인",
+    wizard_cancel_button: "취소",
+    wizard_expired: "죄송합니다. 만료된 버튼입니다.",
+    dialog_cancelled: "진행 중이던 작업이 취소되었습니다.",
+    stale_ballot: "죄송합니다. 투표한 메시지에서 다시 부탁드립니다.",
+    result_header: "*결과 안내*\n",
+    result_line: "{}위: 기호 {}번 후보자 {} ({})\n",
+    vote_count: "{}표",
+    weighted_count: "가중 {}표, 실제 {}표",
+    result_footer: "당선을 축하드립니다!",
+    chart_caption: "{} 개표 결과\n",
+    chart_legend_line: "{}. {}\n",
+    result_void: "투표자 {}명으로 정족수({}명)에 미달하여 이번 투표는 무효입니다.",
+    tie_declared: "기호 {}번 후보가 동점입니다. 남은 {}석은 정해지지 않았습니다.\n",
+    tie_runoff: "기호 {}번 후보가 동점입니다. 남은 {}석을 두고 결선 투표를 진행합니다.\n",
+    tie_drawn: "기호 {}번 후보가 동점이어서 추첨했습니다.\n비밀값: `{}` (투표 시작 때 SHA3-256 해시를 공개함)\n시드: `{}` (비밀값|제목|득표수 목록의 SHA3-256)\n후보마다 SHA3-256(시드|기호)를 계산해 작은 순서로 당선: 기호 {}번\n",
+    majority_runoff: "1위 기호 {}번 후보가 투표자의 {}%를 넘지 못해 기호 {}번 후보 간 결선 투표를 진행합니다.\n",
+    runoff_started: "*{}* 결선 투표가 시작되었습니다. 지금 투표해주세요!",
+    poll_opened: "*{}* 투표가 시작되었습니다. 아래 버튼을 눌러 투표해주세요!",
+    delivery_report: "*{}* 시작 알림: {}명 전송, {}명 실패, {}명 봇 미시작\n",
+    delivery_failed: "\n전송 실패 (봇 차단 등):\n",
+    delivery_unstarted: "\n봇을 시작하지 않은 유권자:\n",
+    undelivered_line: "`{}` {}\n",
+    runoff_title: "{} 결선 투표",
+    option_result_line: "{}: {}\n",
+    referendum_passed: "찬성 {}표 / 찬반 {}표로 *가결*되었습니다. (기준: {})",
+    referendum_failed: "찬성 {}표 / 찬반 {}표로 *부결*되었습니다. (기준: {})",
+    export_usage: "사용법: `/export [투표 번호]`",
+    export_running: "진행 중인 투표는 종료된 뒤에 내보낼 수 있습니다.",
+    export_not_found: "해당 번호의 종료된 투표가 없습니다.",
+    export_caption: "{}번 투표 기록: {}",
+    tally_mismatch: "⚠️ *{}* 집계와 저장된 표가 일치하지 않습니다:\n",
+    close_failed: "⚠️ *{}* 투표를 보관하지 못해 닫지 않고 일시정지해 두었습니다. 지워진 것은 없습니다. /remove 로 다시 닫아주세요.",
+    mismatch_line: "`{}`\n",
+};
@@ -0,0 +1,249 @@
+mod en;
+mod ko;
+
+use std::fmt::Display;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lang {
+    Ko,
+    En,
+}
+
+impl Default for Lang {
+    fn default() -> Self {
+        Lang::Ko
+    }
+}
+
+impl Lang {
+    /// Maps a Telegram `language_code` (e.g. `ko`, `en-US`) to a bundle.
+    /// Anything that isn't Korean falls back to English.
+    pub fn from_code(code: &str) -> Lang {
+        if code.to_lowercase().starts_with("ko") {
+            Lang::Ko
+        } else {
+            Lang::En
+        }
+    }
+
+    /// Parses the argument of `/lang`. Only exact bundle names are accepted.
+    pub fn parse(code: &str) -> Option<Lang> {
+        match code.to_lowercase().as_str() {
+            "ko" => Some(Lang::Ko),
+            "en" => Some(Lang::En),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Lang::Ko => "ko",
+            Lang::En => "en",
+        }
+    }
+
+    pub fn messages(&self) -> &'static Messages {
+        match self {
+            Lang::Ko => &ko::MESSAGES,
+            Lang::En => &en::MESSAGES,
+        }
+    }
+}
+
+/// A message bundle. Templates use `{}` placeholders which are filled in
+/// order by [`fill`].
+pub struct Messages {
+    pub help: &'static str,
+    pub admin_help: &'static str,
+    pub about: &'static str,
+    pub welcome: &'static str,
+    pub unknown_command: &'static str,
+    pub admin_only: &'static str,
+    pub admin_private_only: &'static str,
+    pub private_only: &'static str,
+    pub no_permission: &'static str,
+    pub already_registered: &'static str,
+    pub registered: &'static str,
+    pub no_voters: &'static str,
+    pub voters_header: &'static str,
+    pub voter_line: &'static str,
+    pub voter_suspended_line: &'static str,
+    pub voter_weight: &'static str,
+    pub voter_usage: &'static str,
+    pub voter_not_found: &'static str,
+    pub roster_not_saved: &'static str,
+    pub voter_removed: &'static str,
+    pub voter_suspended: &'static str,
+    pub late_registered: &'static str,
+    pub late_register_notice: &'static str,
+    pub already_elector: &'static str,
+    pub voter_unsuspended: &'static str,
+    pub import_usage: &'static str,
+    pub voters_imported: &'static str,
+    pub weight_usage: &'static str,
+    pub weight_set: &'static str,
+    pub chat_registered: &'static str,
+    pub admin_token: &'static str,
+    pub invalid_token: &'static str,
+    pub lang_set: &'static str,
+    pub lang_usage: &'static str,
+    pub no_poll: &'static str,
+    pub not_started: &'static str,
+    pub not_running: &'static str,
+    pub paused: &'static str,
+    pub already_paused: &'static str,
+    pub not_paused: &'static str,
+    pub poll_paused: &'static str,
+    pub poll_resumed: &'static str,
+    pub set_end_usage: &'static str,
+    pub end_changed: &'static str,
+    pub poll_running: &'static str,
+    pub status_above: &'static str,
+    pub already_running: &'static str,
+    pub poll_not_saved: &'static str,
+    pub need_candidate: &'static str,
+    pub min_duration: &'static str,
+    pub poll_closed: &'static str,
+    pub quorum_extended: &'static str,
+    pub countdown: &'static str,
+    pub reminder: &'static str,
+    pub hours: &'static str,
+    pub minutes: &'static str,
+    pub candidate_count: &'static str,
+    pub paused_line: &'static str,
+    pub runoff_line: &'static str,
+    pub candidate_line: &'static str,
+    pub referendum_header: &'static str,
+    pub option_line: &'static str,
+    pub option_for: &'static str,
+    pub option_against: &'static str,
+    pub option_abstain: &'static str,
+    pub option_nota: &'static str,
+    pub pass_line: &'static str,
+    pub pass_simple: &'static str,
+    pub pass_two_thirds: &'static str,
+    pub pass_percent: &'static str,
+    pub method_approval: &'static str,
+    pub method_single: &'static str,
+    pub electorate_line: &'static str,
+    pub electorate_users: &'static str,
+    pub electorate_admins: &'static str,
+    pub how_to_vote: &'static str,
+    pub start_time: &'static str,
+    pub end_time: &'static str,
+    pub time_left: &'static str,
+    pub turnout: &'static str,
+    pub participation: &'static str,
+    pub quorum_void_line: &'static str,
+    pub quorum_extend_line: &'static str,
+    pub seats_line: &'static str,
+    pub majority_line: &'static str,
+    pub tie_line: &'static str,
+    pub draw_commitment_line: &'static str,
+    pub tie_declare_name: &'static str,
+    pub tie_runoff_name: &'static str,
+    pub tie_draw_name: &'static str,
+    pub go_vote: &'static str,
+    pub vote_button: &'static str,
+    pub check_button: &'static str,
+    pub profile_button: &'static str,
+    pub clear_button: &'static str,
+    pub not_eligible: &'static str,
+    pub link_expired: &'static str,
+    pub check_line: &'static str,
+    pub no_ballot: &'static str,
+    pub cleared: &'static str,
+    pub retracted: &'static str,
+    pub voted: &'static str,
+    pub ballot_failed: &'static str,
+    pub invalid_candidate: &'static str,
+    pub profile_ask_statement: &'static str,
+    pub profile_ask_photo: &'static str,
+    pub profile_saved: &'static str,
+    pub profile_expired: &'static str,
+    pub profile_not_saved: &'static str,
+    pub profile_text: &'static str,
+    pub profile_sent: &'static str,
+    pub no_profile: &'static str,
+    pub wizard_title: &'static str,
+    pub wizard_kind: &'static str,
+    pub kind_election_button: &'static str,
+    pub kind_referendum_button: &'static str,
+    pub wizard_candidates: &'static str,
+    pub wizard_candidate_added: &'static str,
+    pub wizard_method: &'static str,
+    pub method_approval_button: &'static str,
+    pub method_single_button: &'static str,
+    pub wizard_selections: &'static str,
+    pub wizard_start: &'static str,
+    pub wizard_end: &'static str,
+    pub invalid_time: &'static str,
+    pub wizard_electorate: &'static str,
+    pub wizard_rules: &'static str,
+    pub invalid_rule: &'static str,
+    pub wizard_preview: &'static str,
+    pub wizard_confirm_button: &'static str,
+    pub wizard_cancel_button: &'static str,
+    pub wizard_expired: &'static str,
+    pub dialog_cancelled: &'static str,
+    pub stale_ballot: &'static str,
+    pub result_header: &'static str,
+    pub result_line: &'static str,
+    pub vote_count: &'static str,
+    pub weighted_count: &'static str,
+    pub result_footer: &'static str,
+    pub chart_caption: &'static str,
+    pub chart_legend_line: &'static str,
+    pub result_void: &'static str,
+    pub tie_declared: &'static str,
+    pub tie_runoff: &'static str,
+    pub tie_drawn: &'static str,
+    pub majority_runoff: &'static str,
+    pub runoff_started: &'static str,
+    pub poll_opened: &'static str,
+    pub delivery_report: &'static str,
+    pub delivery_failed: &'static str,
+    pub delivery_unstarted: &'static str,
+    pub undelivered_line: &'static str,
+    pub runoff_title: &'static str,
+    pub option_result_line: &'static str,
+    pub referendum_passed: &'static str,
+    pub referendum_failed: &'static str,
+    pub export_usage: &'static str,
+    pub export_running: &'static str,
+    pub export_not_found: &'static str,
+    pub export_caption: &'static str,
+    pub tally_mismatch: &'static str,
+    pub close_failed: &'static str,
+    pub mismatch_line: &'static str,
+}
+
+/// Escapes the characters Telegram's Markdown gives a meaning to, so text
+/// typed by users can be put in a Markdown message as is.
+pub fn escape_markdown(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '_' | '*' | '`' | '[') {
+            res.push('\\');
+        }
+        res.push(c);
+    }
+    res
+}
+
+/// Replaces each `{}` in `template` with the next argument.
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut res = String::new();
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+    if let Some(first) = parts.next() {
+        res.push_str(first);
+    }
+    for part in parts {
+        if let Some(arg) = args.next() {
+            res.push_str(&arg.to_string());
+        }
+        res.push_str(part);
+    }
+    res
+}
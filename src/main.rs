@@ -2,47 +2,51 @@
 extern crate pkg_version;
 
 mod bot;
+mod chart;
+mod cli;
 mod config;
 mod constants;
+mod dialog;
+mod locale;
 mod middlewares;
 mod poll_token;
+mod roster;
 mod token_service;
 
 use futures::StreamExt;
-use tokio::time::{timeout, Duration};
+use std::sync::Arc;
+use tokio::time::{interval, Duration};
 
 use telegram_bot::{
-    Api, Error, Message, MessageKind, Poll, PollAnswer, SendPoll, UpdateKind, User,
+    Api, Error, GetMe, Message, MessageKind, Poll, PollAnswer, SendPoll, Update, UpdateKind, User,
 };
 
 use chrono::prelude::*;
 
-async fn check_poll(bot: &mut bot::Bot, api: Api) {
-    if bot.is_present {
-        //dbg!(bot.poll.end);
-        if bot.poll.end <= Utc::now().timestamp() {
+/// How often the poll is checked for reminders that are due and for having
+/// ended.
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+async fn check_poll(bot: &bot::Bot, api: Api) {
+    // A paused poll stays open past its end until an admin resumes it.
+    if bot.is_present() && !bot.is_paused().await {
+        if bot.poll_end().await <= Utc::now().timestamp() {
             bot.remove_poll(api).await.ok();
         }
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Error> {
-    let config = config::Config::open(constants::CONFIG_PATH).unwrap();
-
-    let api = Api::new(config.token);
-    let mut stream = api.stream();
-    let mut bot = bot::Bot::new();
-    //timeout(Duration::from_secs(1), check_poll(&mut bot, api.clone())).await.ok();
-
-    while let Some(update) = stream.next().await {
-        let update = update?;
-
-        match update.kind {
-            UpdateKind::Message(message) => match message.kind {
+async fn handle_update(bot: &bot::Bot, api: Api, update: Update) -> Result<(), Error> {
+    match update.kind {
+        UpdateKind::Message(message) => {
+            bot.note_private(&message).await;
+            match message.kind {
                 MessageKind::Text { ref data, .. } => match data.split_whitespace().nth(0).unwrap().split('@').nth(0).unwrap()
                 {
-                    "/start" => bot.handle_start(api.clone(), message.clone()).await?,
+                    "/start" => {
+                        bot.handle_start(api.clone(), message.clone(), data.to_string())
+                            .await?
+                    }
                     "/register_chat" => {
                         bot.handle_register_chat(api.clone(), message.clone())
                             .await?
@@ -57,6 +61,54 @@ async fn main() -> Result<(), Error> {
                     "/help" => bot.handle_help(api.clone(), message.clone()).await?,
                     "/about" => bot.handle_about(api.clone(), message.clone()).await?,
                     "/admin_help" => bot.handle_admin_help(api.clone(), message.clone()).await?,
+                    "/profile" => {
+                        bot.handle_profile(api.clone(), message.clone(), data.to_string())
+                            .await?
+                    }
+                    "/skip" | "/done" => {
+                        bot.handle_dialog(api.clone(), message.clone(), data.to_string())
+                            .await?
+                    }
+                    "/cancel" => bot.handle_cancel(api.clone(), message.clone()).await?,
+                    "/voters" => bot.handle_voters(api.clone(), message.clone()).await?,
+                    "/remove_voter" => {
+                        bot.handle_remove_voter(api.clone(), message.clone(), data.to_string())
+                            .await?
+                    }
+                    "/suspend" => {
+                        bot.handle_suspend(api.clone(), message.clone(), data.to_string(), true)
+                            .await?
+                    }
+                    "/unsuspend" => {
+                        bot.handle_suspend(api.clone(), message.clone(), data.to_string(), false)
+                            .await?
+                    }
+                    "/import_voters" => {
+                        bot.handle_import_voters(api.clone(), message.clone(), data.to_string())
+                            .await?
+                    }
+                    "/pause" => bot.handle_pause(api.clone(), message.clone(), true).await?,
+                    "/resume" => bot.handle_pause(api.clone(), message.clone(), false).await?,
+                    "/set_end" => {
+                        bot.handle_set_end(api.clone(), message.clone(), data.to_string())
+                            .await?
+                    }
+                    "/weight" => {
+                        bot.handle_weight(api.clone(), message.clone(), data.to_string())
+                            .await?
+                    }
+                    "/export" => {
+                        bot.handle_export(api.clone(), message.clone(), data.to_string())
+                            .await?
+                    }
+                    "/late_register" => {
+                        bot.handle_late_register(api.clone(), message.clone(), data.to_string())
+                            .await?
+                    }
+                    "/lang" => {
+                        bot.handle_lang(api.clone(), message.clone(), data.to_string())
+                            .await?
+                    }
                     /*"/vote" => {
                         bot.handle_vote(api.clone(), message.clone(), data.to_string())
                             .await?
@@ -72,34 +124,108 @@ async fn main() -> Result<(), Error> {
                         bot.handle_accept_admin(api.clone(), message.clone(), data.to_string())
                             .await?
                     }
+                    command if !command.starts_with('/') => {
+                        bot.handle_dialog(api.clone(), message.clone(), data.to_string())
+                            .await?
+                    }
                     _ => {}
                 },
-                _ => (),
-            },
-            UpdateKind::CallbackQuery(callback) => match callback
-                .data
-                .clone()
-                .unwrap()
-                .split_whitespace()
-                .nth(0)
-                .unwrap()
-            {
-                "/vote" => {
-                    bot.handle_vote_callback(api.clone(), callback.clone())
-                        .await?
-                }
-                "/check" => {
-                    bot.handle_check_callback(api.clone(), callback.clone())
-                        .await?
-                }
-                "/clear" => {
-                    bot.handle_clear_callback(api.clone(), callback.clone())
-                        .await?
+                MessageKind::Photo { ref data, .. } => {
+                    if let Some(photo) = data.last() {
+                        bot.handle_dialog_photo(api.clone(), message.clone(), photo.file_id.clone())
+                            .await?
+                    }
                 }
                 _ => (),
-            },
+            }
+        }
+        UpdateKind::CallbackQuery(callback) => match callback
+            .data
+            .clone()
+            .unwrap()
+            .split_whitespace()
+            .nth(0)
+            .unwrap()
+        {
+            "/vote" => {
+                bot.handle_vote_callback(api.clone(), callback.clone())
+                    .await?
+            }
+            "/check" => {
+                bot.handle_check_callback(api.clone(), callback.clone())
+                    .await?
+            }
+            "/clear" => {
+                bot.handle_clear_callback(api.clone(), callback.clone())
+                    .await?
+            }
+            "/wizard" => {
+                bot.handle_wizard_callback(api.clone(), callback.clone())
+                    .await?
+            }
+            "/profile" => {
+                bot.handle_profile_callback(api.clone(), callback.clone())
+                    .await?
+            }
             _ => (),
+        },
+        _ => (),
+    }
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
+
+    let config = match config::Config::open(constants::CONFIG_PATH) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
         }
+    };
+
+    let api = Api::new(config.token.clone());
+    let me = api.send(GetMe).await?;
+    let mut stream = api.stream();
+    let bot = Arc::new(bot::Bot::new(me, config));
+    bot.reconcile(&api).await?;
+
+    {
+        let bot = bot.clone();
+        let api = api.clone();
+        tokio::spawn(async move {
+            let mut ticks = interval(CHECK_INTERVAL);
+            loop {
+                ticks.tick().await;
+                if let Err(err) = bot.announce_poll(&api).await {
+                    eprintln!("failed to announce poll: {}", err);
+                }
+                if let Err(err) = bot.send_reminders(&api).await {
+                    eprintln!("failed to send reminders: {}", err);
+                }
+                bot.refresh_status(&api, false).await.ok();
+                check_poll(&bot, api.clone()).await;
+            }
+        });
+    }
+
+    while let Some(update) = stream.next().await {
+        let update = update?;
+        let bot = bot.clone();
+        let api = api.clone();
+
+        // Each update gets its own task so a slow request never blocks other voters.
+        tokio::spawn(async move {
+            if let Err(err) = handle_update(&bot, api, update).await {
+                eprintln!("failed to handle update: {}", err);
+            }
+        });
     }
 
     Ok(())
@@ -1,10 +1,216 @@
-use crate::bot::PollService;
+use crate::bot::{Candidate, Choice, Electorate, Kind, Method, PollService, Rules};
+use crate::config::Config;
 use crate::constants::*;
 use crate::poll_token::PollToken;
+use crate::roster::Roster;
+use chrono::prelude::*;
+use serde::Serialize;
+use sha3::{Digest, Sha3_256};
 use sqlite::{Connection, OpenFlags};
+use std::fmt;
 use std::fs::File;
 use std::path::Path;
-use telegram_bot::types::{MessageId, UserId};
+use telegram_bot::types::{ChatId, MessageId, UserId};
+
+/// Schema migrations, applied in order. `PRAGMA user_version` records how
+/// many of them the database has already seen.
+const MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE IF NOT EXISTS votes (token TEXT, user INTEGER, msg_id INTEGER);
+    CREATE TABLE IF NOT EXISTS stats (id INTEGER, name TEXT, votes INTEGER);
+    CREATE TABLE IF NOT EXISTS info (start INTEGER, end INTEGER, key TEXT);
+    ",
+    "
+    CREATE TABLE langs (user INTEGER PRIMARY KEY, lang TEXT);
+    ",
+    "
+    ALTER TABLE info ADD COLUMN title TEXT;
+    ",
+    "
+    ALTER TABLE stats ADD COLUMN statement TEXT;
+    ALTER TABLE stats ADD COLUMN photo TEXT;
+    ",
+    "
+    ALTER TABLE info ADD COLUMN method TEXT;
+    ALTER TABLE info ADD COLUMN selections INTEGER;
+    ALTER TABLE info ADD COLUMN electorate TEXT;
+    ",
+    "
+    CREATE TABLE electors (user INTEGER PRIMARY KEY, name TEXT);
+    CREATE TABLE audit (id INTEGER PRIMARY KEY AUTOINCREMENT, time INTEGER, admin INTEGER, action TEXT, detail TEXT, hash TEXT);
+    ",
+    "
+    ALTER TABLE electors ADD COLUMN voted INTEGER NOT NULL DEFAULT 0;
+    ",
+    "
+    ALTER TABLE info ADD COLUMN rules TEXT;
+    ALTER TABLE info ADD COLUMN extended INTEGER NOT NULL DEFAULT 0;
+    ",
+    "
+    CREATE TABLE polls (id INTEGER PRIMARY KEY AUTOINCREMENT, parent INTEGER, title TEXT, start INTEGER, end INTEGER);
+    ALTER TABLE info ADD COLUMN poll INTEGER;
+    INSERT INTO polls (title, start, end) SELECT title, start, end FROM info;
+    UPDATE info SET poll = last_insert_rowid();
+    ",
+    "
+    ALTER TABLE info ADD COLUMN kind TEXT;
+    ",
+    "
+    ALTER TABLE stats ADD COLUMN choice TEXT;
+    ",
+    "
+    ALTER TABLE electors ADD COLUMN weight INTEGER NOT NULL DEFAULT 1;
+    ALTER TABLE stats ADD COLUMN weighted INTEGER NOT NULL DEFAULT 0;
+    UPDATE stats SET weighted = votes;
+    ",
+    "
+    ALTER TABLE info ADD COLUMN paused INTEGER NOT NULL DEFAULT 0;
+    ",
+    "
+    CREATE TABLE reminders (lead INTEGER, user INTEGER, PRIMARY KEY (lead, user));
+    CREATE TABLE countdowns (lead INTEGER PRIMARY KEY);
+    ",
+    "
+    CREATE TABLE started (user INTEGER PRIMARY KEY, blocked INTEGER NOT NULL DEFAULT 0);
+    INSERT OR IGNORE INTO started (user) SELECT user FROM votes;
+    INSERT OR IGNORE INTO started (user) SELECT user FROM electors WHERE voted = 1;
+    ALTER TABLE info ADD COLUMN announced INTEGER NOT NULL DEFAULT 0;
+    ",
+    "ALTER TABLE info ADD COLUMN status INTEGER;",
+    "
+    ALTER TABLE polls ADD COLUMN kind TEXT;
+    ALTER TABLE polls ADD COLUMN method TEXT;
+    ALTER TABLE polls ADD COLUMN selections INTEGER;
+    ALTER TABLE polls ADD COLUMN electorate TEXT;
+    ALTER TABLE polls ADD COLUMN rules TEXT;
+    ALTER TABLE polls ADD COLUMN eligible INTEGER;
+    ALTER TABLE polls ADD COLUMN voters INTEGER;
+    ALTER TABLE polls ADD COLUMN participants INTEGER;
+    ALTER TABLE polls ADD COLUMN closed INTEGER;
+    CREATE TABLE results (poll INTEGER, id INTEGER, name TEXT, choice TEXT, votes INTEGER, weighted INTEGER, PRIMARY KEY (poll, id));
+    CREATE TABLE ballots (poll INTEGER, token TEXT);
+    ",
+    "
+    CREATE TABLE users (user INTEGER PRIMARY KEY, name TEXT, suspended INTEGER NOT NULL DEFAULT 0, weight INTEGER NOT NULL DEFAULT 1);
+    CREATE TABLE admins (user INTEGER PRIMARY KEY, name TEXT);
+    CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT);
+    ",
+    "
+    ALTER TABLE info ADD COLUMN draw_secret TEXT;
+    UPDATE info SET draw_secret = lower(hex(randomblob(16)));
+    ",
+];
+
+/// Empties the tables of the running poll.
+const CLEAR: &str = "
+    DELETE FROM info;
+    DELETE FROM stats;
+    DELETE FROM votes;
+    DELETE FROM electors;
+    DELETE FROM reminders;
+    DELETE FROM countdowns;
+";
+
+/// Escapes a string for use inside a single-quoted SQL literal.
+fn escape(s: &str) -> String {
+    s.replace('\'', "''")
+}
+
+/// Quotes an optional string as an SQL literal, `NULL` when absent.
+fn nullable(s: &Option<String>) -> String {
+    match s {
+        Some(s) => format!("'{}'", escape(s)),
+        None => "NULL".to_string(),
+    }
+}
+
+/// Hash of an audit entry. Every entry covers the hash of the one before it,
+/// so editing or dropping an entry breaks the chain from there on.
+pub fn audit_hash(prev: &str, time: i64, admin: UserId, action: &str, detail: &str) -> String {
+    let mut hasher = Sha3_256::new();
+    hasher.update(format!("{}|{}|{}|{}|{}", prev, time, admin, action, detail).as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// SHA3-256 of a ballot token, hex encoded.
+pub fn ballot_hash(token: &str) -> String {
+    hex::encode(Sha3_256::digest(token.as_bytes()))
+}
+
+/// A closed poll as archived by [`DbService::archive`].
+#[derive(Serialize, Clone, Debug)]
+pub struct PollRecord {
+    pub id: i64,
+    pub parent: Option<i64>,
+    pub title: String,
+    pub kind: String,
+    pub method: String,
+    pub selections: i64,
+    pub electorate: String,
+    pub rules: String,
+    pub start: i64,
+    pub end: i64,
+    pub closed: i64,
+    pub eligible: i64,
+    /// Electors holding a ballot when the poll closed.
+    pub voters: i64,
+    /// Electors that voted at any point, including cleared ballots.
+    pub participants: i64,
+    pub candidates: Vec<CandidateRecord>,
+    pub ballots: Vec<BallotRecord>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CandidateRecord {
+    pub number: i64,
+    pub name: String,
+    pub choice: String,
+    pub votes: i64,
+    pub weighted: i64,
+}
+
+/// An encrypted ballot, detached from its voter.
+#[derive(Serialize, Clone, Debug)]
+pub struct BallotRecord {
+    pub token: String,
+    pub sha3_256: String,
+}
+
+/// A disagreement between the counters in `stats` and the ballots stored
+/// in `votes`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mismatch {
+    /// Ballots held by a user who isn't an elector.
+    NotElector(UserId, i64),
+    /// A voter holding more ballots than the method allows.
+    OverLimit(UserId, i64),
+    /// Stored ballots and the vote counters disagree.
+    Votes { ballots: i64, counted: i64 },
+    /// Stored ballots and the weighted counters disagree.
+    Weighted { ballots: i64, counted: i64 },
+    /// A counter below zero, by candidate number.
+    Negative(usize),
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mismatch::NotElector(id, n) => {
+                write!(f, "voter {} holds {} ballots but is not an elector", id, n)
+            }
+            Mismatch::OverLimit(id, n) => write!(f, "voter {} holds {} ballots, over the limit", id, n),
+            Mismatch::Votes { ballots, counted } => {
+                write!(f, "{} ballots stored but {} votes counted", ballots, counted)
+            }
+            Mismatch::Weighted { ballots, counted } => write!(
+                f,
+                "{} weighted ballots stored but {} weighted votes counted",
+                ballots, counted
+            ),
+            Mismatch::Negative(i) => write!(f, "candidate {} has a negative count", i),
+        }
+    }
+}
 
 pub struct DbService {
     db: Connection,
@@ -12,28 +218,54 @@ pub struct DbService {
 
 impl DbService {
     pub fn new() -> Result<Self, sqlite::Error> {
-        let mut is_exists = false;
         if !Path::new(DB_PATH).exists() {
             File::create(DB_PATH).ok();
-        } else {
-            is_exists = true;
         }
         let db = Connection::open_with_flags(DB_PATH, OpenFlags::new().set_read_write())?;
         let mut t = Self { db };
-        if !is_exists {
-            t.init().ok();
+        t.init()?;
+        Ok(t)
+    }
+
+    /// Opens the database for reading only, without migrating it. Fails if
+    /// its schema is not the one this build expects.
+    pub fn open_read_only() -> Result<Self, sqlite::Error> {
+        let db = Connection::open_with_flags(DB_PATH, OpenFlags::new().set_read_only())?;
+        let mut t = Self { db };
+        let version = t.version()?;
+        if version != MIGRATIONS.len() {
+            return Err(sqlite::Error {
+                code: None,
+                message: Some(format!(
+                    "schema version {} doesn't match {} expected by this build; start the bot once to migrate it",
+                    version,
+                    MIGRATIONS.len()
+                )),
+            });
         }
         Ok(t)
     }
 
+    /// Number of migrations the database has seen.
+    fn version(&mut self) -> Result<usize, sqlite::Error> {
+        let mut version = 0;
+        self.db.iterate("PRAGMA user_version;", |pairs| {
+            version = pairs[0].1.unwrap().parse::<usize>().unwrap();
+            true
+        })?;
+        Ok(version)
+    }
+
     pub fn init(&mut self) -> Result<(), sqlite::Error> {
-        self.db.execute(
-            "
-        CREATE TABLE votes (token TEXT, user INTEGER, msg_id INTEGER);
-        CREATE TABLE stats (id INTEGER, name TEXT, votes INTEGER);
-        CREATE TABLE info (start INTEGER, end INTEGER, key TEXT);
-        ",
-        )
+        let version = self.version()?;
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            self.db.execute(&format!(
+                "BEGIN; {} PRAGMA user_version = {}; COMMIT;",
+                migration,
+                i + 1
+            ))?;
+        }
+        Ok(())
     }
 
     pub fn is_present(&mut self) -> Result<bool, sqlite::Error> {
@@ -45,56 +277,679 @@ impl DbService {
         Ok(exists)
     }
 
-    pub fn create(&mut self, poll: PollService) -> Result<(), sqlite::Error> {
+    /// Stores a new running poll and registers it in `polls`. Returns the
+    /// id it was registered under.
+    pub fn create(&mut self, poll: PollService) -> Result<i64, sqlite::Error> {
         self.db.execute(&format!(
-            "INSERT INTO info VALUES ({}, {}, '{}');",
-            poll.start, poll.end, poll.key
+            "INSERT INTO polls (parent, title, start, end) VALUES ({}, '{}', {}, {});",
+            poll.parent.map(|p| p.to_string()).unwrap_or_else(|| "NULL".to_string()),
+            escape(&poll.title),
+            poll.start,
+            poll.end
         ))?;
+        let mut id = 0;
+        self.db.iterate("SELECT last_insert_rowid()", |pairs| {
+            id = pairs[0].1.unwrap().parse::<i64>().unwrap();
+            true
+        })?;
+        self.db.execute(&format!(
+            "INSERT INTO info (start, end, key, title, method, selections, electorate, rules, poll, kind, announced, draw_secret) VALUES ({}, {}, '{}', '{}', '{}', {}, '{}', '{}', {}, '{}', {}, '{}');",
+            poll.start,
+            poll.end,
+            poll.key,
+            escape(&poll.title),
+            poll.method.as_str(),
+            poll.selections,
+            poll.electorate.as_str(),
+            escape(&poll.rules.to_string()),
+            id,
+            poll.kind.as_str(),
+            poll.announced as i64,
+            poll.draw_secret
+        ))?;
+        for (id, name) in poll.electors.iter() {
+            self.add_elector(*id, name, poll.weight(*id))?;
+        }
         for i in 0..poll.candidates.len() {
             self.db.execute(&format!(
-                "INSERT INTO stats VALUES ({}, '{}', 0);",
+                "INSERT INTO stats (id, name, votes, statement, photo, choice) VALUES ({}, '{}', 0, {}, {}, '{}');",
                 i + 1,
-                poll.candidates[i]
+                escape(&poll.candidates[i].name),
+                nullable(&poll.candidates[i].statement),
+                nullable(&poll.candidates[i].photo),
+                poll.candidates[i].choice.as_str()
             ))?;
         }
-        Ok(())
+        Ok(id)
     }
 
     pub fn clear(&mut self) -> Result<(), sqlite::Error> {
-        self.db.execute(
+        self.db.execute(CLEAR)?;
+        Ok(())
+    }
+
+    /// Copies the closing state of the running poll into `polls`, `results`
+    /// and `ballots`, then clears it, all in one transaction: if anything
+    /// fails the running poll is left as it was. Ballots are stored without
+    /// their voters, sorted so their order says nothing about when they
+    /// were cast.
+    pub fn archive(&mut self, poll: &PollService) -> Result<(), sqlite::Error> {
+        let (voters, participants) = self.turnout()?;
+        self.transaction(&format!(
             "
-        DELETE FROM info;
-        DELETE FROM stats;
-        DELETE FROM votes;
-        ",
+            UPDATE polls SET kind = '{}', method = '{}', selections = {}, electorate = '{}', rules = '{}', end = {}, eligible = {}, voters = {}, participants = {}, closed = {} WHERE id = {};
+            INSERT INTO results SELECT {}, id, name, choice, votes, weighted FROM stats;
+            INSERT INTO ballots SELECT {}, token FROM votes ORDER BY token;
+            {}
+            ",
+            poll.kind.as_str(),
+            poll.method.as_str(),
+            poll.selections,
+            poll.electorate.as_str(),
+            escape(&poll.rules.to_string()),
+            poll.end,
+            poll.electors.len(),
+            voters,
+            participants,
+            Utc::now().timestamp(),
+            poll.id,
+            poll.id,
+            poll.id,
+            CLEAR
+        ))
+    }
+
+    /// The most recently closed poll.
+    pub fn last_closed(&mut self) -> Result<Option<i64>, sqlite::Error> {
+        let mut res = None;
+        self.db.iterate(
+            "SELECT id FROM polls WHERE closed IS NOT NULL ORDER BY closed DESC LIMIT 1",
+            |pairs| {
+                res = Some(pairs[0].1.unwrap().parse::<i64>().unwrap());
+                true
+            },
+        )?;
+        Ok(res)
+    }
+
+    /// Loads an archived poll. Polls that are still running or were never
+    /// archived give `None`.
+    pub fn record(&mut self, id: i64) -> Result<Option<PollRecord>, sqlite::Error> {
+        let num = |s: Option<&str>| s.and_then(|s| s.parse::<i64>().ok()).unwrap_or(0);
+        let text = |s: Option<&str>| s.unwrap_or("").to_string();
+        let mut res = None;
+        self.db.iterate(
+            &format!(
+                "SELECT id, parent, title, kind, method, selections, electorate, rules, start, end, closed, eligible, voters, participants
+                FROM polls WHERE id = {} AND closed IS NOT NULL",
+                id
+            ),
+            |pairs| {
+                res = Some(PollRecord {
+                    id: num(pairs[0].1),
+                    parent: pairs[1].1.map(|p| p.parse::<i64>().unwrap()),
+                    title: text(pairs[2].1),
+                    kind: text(pairs[3].1),
+                    method: text(pairs[4].1),
+                    selections: num(pairs[5].1),
+                    electorate: text(pairs[6].1),
+                    rules: text(pairs[7].1),
+                    start: num(pairs[8].1),
+                    end: num(pairs[9].1),
+                    closed: num(pairs[10].1),
+                    eligible: num(pairs[11].1),
+                    voters: num(pairs[12].1),
+                    participants: num(pairs[13].1),
+                    candidates: vec![],
+                    ballots: vec![],
+                });
+                true
+            },
+        )?;
+        let mut record = match res {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        self.db.iterate(
+            &format!(
+                "SELECT id, name, choice, votes, weighted FROM results WHERE poll = {} ORDER BY id",
+                id
+            ),
+            |pairs| {
+                record.candidates.push(CandidateRecord {
+                    number: num(pairs[0].1),
+                    name: text(pairs[1].1),
+                    choice: pairs[2].1.unwrap_or(Choice::Candidate.as_str()).to_string(),
+                    votes: num(pairs[3].1),
+                    weighted: num(pairs[4].1),
+                });
+                true
+            },
         )?;
+        self.db.iterate(
+            &format!("SELECT token FROM ballots WHERE poll = {} ORDER BY token", id),
+            |pairs| {
+                let token = text(pairs[0].1);
+                record.ballots.push(BallotRecord {
+                    sha3_256: ballot_hash(&token),
+                    token,
+                });
+                true
+            },
+        )?;
+        Ok(Some(record))
+    }
+
+    /// Runs `sql` as a single transaction, rolling it back if any
+    /// statement fails.
+    fn transaction(&mut self, sql: &str) -> Result<(), sqlite::Error> {
+        let res = self.db.execute(&format!("BEGIN; {} COMMIT;", sql));
+        if res.is_err() {
+            self.db.execute("ROLLBACK;").ok();
+        }
+        res
+    }
+
+    /// Stores a ballot together with the new counters of candidate `idx`.
+    pub fn cast(
+        &mut self,
+        token: &PollToken,
+        idx: usize,
+        val: i64,
+        weighted: i64,
+    ) -> Result<(), sqlite::Error> {
+        self.transaction(&format!(
+            "
+            UPDATE stats SET votes = {}, weighted = {} WHERE id = {};
+            UPDATE electors SET voted = 1 WHERE user = {};
+            INSERT INTO votes VALUES ('{}', {}, {});
+            ",
+            val, weighted, idx, token.user_id, token.token, token.user_id, token.msg_id
+        ))
+    }
+
+    /// Removes a ballot together with updating the counters of candidate
+    /// `idx`.
+    pub fn retract(
+        &mut self,
+        token: &PollToken,
+        idx: usize,
+        val: i64,
+        weighted: i64,
+    ) -> Result<(), sqlite::Error> {
+        self.transaction(&format!(
+            "
+            UPDATE stats SET votes = {}, weighted = {} WHERE id = {};
+            DELETE FROM votes WHERE user = {} AND token = '{}';
+            ",
+            val, weighted, idx, token.user_id, token.token
+        ))
+    }
+
+    /// Compares the counters in `stats` with the stored ballots. `limit` is
+    /// the most ballots one voter may hold.
+    pub fn reconcile(&mut self, limit: usize) -> Result<Vec<Mismatch>, sqlite::Error> {
+        let mut res = vec![];
+        let (mut ballots, mut weighted) = (0, 0);
+        for (voter, count, weight) in self.ballot_counts()? {
+            ballots += count;
+            match weight {
+                Some(weight) => weighted += count * weight,
+                None => res.push(Mismatch::NotElector(voter, count)),
+            }
+            if count as usize > limit {
+                res.push(Mismatch::OverLimit(voter, count));
+            }
+        }
+        let (mut votes, mut counted) = (0, 0);
+        self.db
+            .iterate("SELECT id, votes, weighted FROM stats ORDER BY id", |pairs| {
+                let id = pairs[0].1.unwrap().parse::<usize>().unwrap();
+                let v = pairs[1].1.unwrap().parse::<i64>().unwrap();
+                let w = pairs[2].1.unwrap().parse::<i64>().unwrap();
+                if v < 0 || w < 0 {
+                    res.push(Mismatch::Negative(id));
+                }
+                votes += v;
+                counted += w;
+                true
+            })?;
+        if ballots != votes {
+            res.push(Mismatch::Votes {
+                ballots,
+                counted: votes,
+            });
+        }
+        if weighted != counted {
+            res.push(Mismatch::Weighted {
+                ballots: weighted,
+                counted,
+            });
+        }
+        Ok(res)
+    }
+
+    /// Moves the end of the poll when it is extended to reach quorum.
+    pub fn extend(&mut self, end: i64) -> Result<(), sqlite::Error> {
+        self.db.execute(&format!(
+            "UPDATE info SET end = {}, extended = 1;",
+            end
+        ))?;
+        Ok(())
+    }
+
+    pub fn set_end(&mut self, end: i64) -> Result<(), sqlite::Error> {
+        self.db
+            .execute(&format!("UPDATE info SET end = {};", end))?;
+        Ok(())
+    }
+
+    pub fn set_announced(&mut self) -> Result<(), sqlite::Error> {
+        self.db.execute("UPDATE info SET announced = 1;")?;
+        Ok(())
+    }
+
+    pub fn set_status(&mut self, id: i64) -> Result<(), sqlite::Error> {
+        self.db.execute(&format!("UPDATE info SET status = {};", id))?;
         Ok(())
     }
 
-    pub fn update(&mut self, idx: usize, val: i64) -> Result<(), sqlite::Error> {
+    pub fn set_paused(&mut self, paused: bool) -> Result<(), sqlite::Error> {
+        self.db
+            .execute(&format!("UPDATE info SET paused = {};", paused as i64))?;
+        Ok(())
+    }
+
+    pub fn set_profile(
+        &mut self,
+        idx: usize,
+        statement: &Option<String>,
+        photo: &Option<String>,
+    ) -> Result<(), sqlite::Error> {
         self.db.execute(&format!(
-            "UPDATE stats SET votes = {} WHERE id = {};",
-            val, idx
+            "UPDATE stats SET statement = {}, photo = {} WHERE id = {};",
+            nullable(statement),
+            nullable(photo),
+            idx
         ))?;
         Ok(())
     }
 
     pub fn load(&mut self) -> Result<PollService, sqlite::Error> {
-        let mut poll = PollService::new(vec![], 0, 0);
-        self.db.iterate("SELECT * FROM info", |pairs| {
-            poll.start = pairs[0].1.unwrap().parse::<i64>().unwrap();
-            poll.end = pairs[1].1.unwrap().parse::<i64>().unwrap();
-            poll.key = pairs[2].1.unwrap().to_string();
-            true
-        })?;
-        self.db.iterate("SELECT * FROM stats", |pairs| {
-            poll.candidates.push(pairs[1].1.unwrap().to_string());
-            poll.votes.push(pairs[2].1.unwrap().parse::<i64>().unwrap());
+        let mut poll = PollService::new(String::new(), vec![], 0, 0);
+        self.db.iterate(
+            "SELECT start, end, key, title, method, selections, electorate, rules, extended, poll, kind, paused, announced, status, draw_secret FROM info",
+            |pairs| {
+                poll.start = pairs[0].1.unwrap().parse::<i64>().unwrap();
+                poll.end = pairs[1].1.unwrap().parse::<i64>().unwrap();
+                poll.key = pairs[2].1.unwrap().to_string();
+                poll.title = pairs[3].1.unwrap_or("").to_string();
+                if let Some(method) = pairs[4].1.and_then(Method::parse) {
+                    poll.method = method;
+                }
+                if let Some(selections) = pairs[5].1.and_then(|s| s.parse::<usize>().ok()) {
+                    poll.selections = selections;
+                }
+                if let Some(electorate) = pairs[6].1.and_then(Electorate::parse) {
+                    poll.electorate = electorate;
+                }
+                poll.rules = Rules::parse(pairs[7].1.unwrap_or(""));
+                poll.extended = pairs[8].1 == Some("1");
+                poll.id = pairs[9].1.and_then(|id| id.parse::<i64>().ok()).unwrap_or(0);
+                if let Some(kind) = pairs[10].1.and_then(Kind::parse) {
+                    poll.kind = kind;
+                }
+                poll.paused = pairs[11].1 == Some("1");
+                poll.announced = pairs[12].1 == Some("1");
+                poll.status = pairs[13].1.map(|s| s.parse::<i64>().unwrap());
+                poll.draw_secret = pairs[14].1.unwrap_or("").to_string();
+                true
+            },
+        )?;
+        self.db.iterate(
+            "SELECT id, name, votes, statement, photo, choice, weighted FROM stats ORDER BY id",
+            |pairs| {
+                poll.candidates.push(Candidate {
+                    name: pairs[1].1.unwrap().to_string(),
+                    choice: pairs[5].1.and_then(Choice::parse).unwrap_or(Choice::Candidate),
+                    statement: pairs[3].1.map(|s| s.to_string()),
+                    photo: pairs[4].1.map(|s| s.to_string()),
+                });
+                poll.votes.push(pairs[2].1.unwrap().parse::<i64>().unwrap());
+                poll.weighted.push(pairs[6].1.unwrap().parse::<i64>().unwrap());
+                true
+            },
+        )?;
+        self.db.iterate(
+            &format!("SELECT parent FROM polls WHERE id = {}", poll.id),
+            |pairs| {
+                poll.parent = pairs[0].1.and_then(|p| p.parse::<i64>().ok());
+                true
+            },
+        )?;
+        self.db.iterate("SELECT user, name, weight FROM electors", |pairs| {
+            let id = UserId::new(pairs[0].1.unwrap().parse::<i64>().unwrap());
+            poll.electors.insert(id, pairs[1].1.unwrap_or("").to_string());
+            let weight = pairs[2].1.unwrap().parse::<i64>().unwrap();
+            if weight != 1 {
+                poll.weights.insert(id, weight);
+            }
             true
         })?;
         Ok(poll)
     }
 
+    pub fn add_elector(&mut self, id: UserId, name: &str, weight: i64) -> Result<(), sqlite::Error> {
+        self.db.execute(&format!(
+            "INSERT OR REPLACE INTO electors (user, name, weight) VALUES ({}, '{}', {});",
+            id,
+            escape(name),
+            weight
+        ))?;
+        Ok(())
+    }
+
+    /// Returns how many eligible voters currently hold a ballot and how many
+    /// have voted at least once.
+    pub fn turnout(&mut self) -> Result<(usize, usize), sqlite::Error> {
+        let mut current = 0;
+        let mut ever = 0;
+        self.db.iterate(
+            "SELECT COUNT(DISTINCT votes.user) FROM votes JOIN electors ON votes.user = electors.user",
+            |pairs| {
+                current = pairs[0].1.unwrap().parse::<usize>().unwrap();
+                true
+            },
+        )?;
+        self.db
+            .iterate("SELECT COUNT(*) FROM electors WHERE voted = 1", |pairs| {
+                ever = pairs[0].1.unwrap().parse::<usize>().unwrap();
+                true
+            })?;
+        Ok((current, ever))
+    }
+
+    /// Total weight of the eligible voters that currently hold a ballot.
+    pub fn weighted_voters(&mut self) -> Result<i64, sqlite::Error> {
+        let mut weight = 0;
+        self.db.iterate(
+            "SELECT COALESCE(SUM(weight), 0) FROM electors WHERE user IN (SELECT user FROM votes)",
+            |pairs| {
+                weight = pairs[0].1.unwrap().parse::<i64>().unwrap();
+                true
+            },
+        )?;
+        Ok(weight)
+    }
+
+    /// Electors without a ballot that haven't had the reminder sent `lead`
+    /// minutes before the end yet.
+    pub fn unreminded(&mut self, lead: i64) -> Result<Vec<UserId>, sqlite::Error> {
+        let mut res = vec![];
+        self.db.iterate(
+            &format!(
+                "SELECT user FROM electors
+                WHERE user NOT IN (SELECT user FROM votes)
+                AND user NOT IN (SELECT user FROM reminders WHERE lead = {})",
+                lead
+            ),
+            |pairs| {
+                res.push(UserId::new(pairs[0].1.unwrap().parse::<i64>().unwrap()));
+                true
+            },
+        )?;
+        Ok(res)
+    }
+
+    pub fn mark_reminded(&mut self, lead: i64, id: UserId) -> Result<(), sqlite::Error> {
+        self.db.execute(&format!(
+            "INSERT OR IGNORE INTO reminders VALUES ({}, {});",
+            lead, id
+        ))?;
+        Ok(())
+    }
+
+    /// Records the group countdown for `lead`. Returns false if it was
+    /// already posted.
+    pub fn mark_countdown(&mut self, lead: i64) -> Result<bool, sqlite::Error> {
+        let mut posted = false;
+        self.db.iterate(
+            &format!("SELECT lead FROM countdowns WHERE lead = {}", lead),
+            |_| {
+                posted = true;
+                true
+            },
+        )?;
+        if !posted {
+            self.db
+                .execute(&format!("INSERT INTO countdowns VALUES ({});", lead))?;
+        }
+        Ok(!posted)
+    }
+
+    /// Records that a user has a private chat with the bot, clearing any
+    /// earlier delivery failure.
+    pub fn mark_started(&mut self, id: UserId) -> Result<(), sqlite::Error> {
+        self.db
+            .execute(&format!("INSERT OR REPLACE INTO started VALUES ({}, 0);", id))?;
+        Ok(())
+    }
+
+    /// Records that a message to a user could not be delivered, usually
+    /// because they blocked the bot.
+    pub fn mark_blocked(&mut self, id: UserId) -> Result<(), sqlite::Error> {
+        self.db
+            .execute(&format!("INSERT OR REPLACE INTO started VALUES ({}, 1);", id))?;
+        Ok(())
+    }
+
+    /// Users that have started the bot and haven't failed a delivery since.
+    pub fn reachable(&mut self) -> Result<Vec<UserId>, sqlite::Error> {
+        let mut res = vec![];
+        self.db
+            .iterate("SELECT user FROM started WHERE blocked = 0", |pairs| {
+                res.push(UserId::new(pairs[0].1.unwrap().parse::<i64>().unwrap()));
+                true
+            })?;
+        Ok(res)
+    }
+
+    /// Appends an entry to the audit trail.
+    pub fn audit(&mut self, admin: UserId, action: &str, detail: &str) -> Result<(), sqlite::Error> {
+        let mut prev = String::new();
+        self.db
+            .iterate("SELECT hash FROM audit ORDER BY id DESC LIMIT 1", |pairs| {
+                prev = pairs[0].1.unwrap_or("").to_string();
+                true
+            })?;
+        let time = Utc::now().timestamp();
+        self.db.execute(&format!(
+            "INSERT INTO audit (time, admin, action, detail, hash) VALUES ({}, {}, '{}', '{}', '{}');",
+            time,
+            admin,
+            escape(action),
+            escape(detail),
+            audit_hash(&prev, time, admin, action, detail)
+        ))?;
+        Ok(())
+    }
+
+    /// Copies the roster kept in `Bot.toml` by older versions into the
+    /// database. Does nothing once it has been done, so the file can be
+    /// cleaned up afterwards. The admins listed in the file are added on
+    /// every start.
+    pub fn import_config(&mut self, config: &Config) -> Result<(), sqlite::Error> {
+        let mut imported = false;
+        self.db
+            .iterate("SELECT value FROM settings WHERE key = 'imported'", |_| {
+                imported = true;
+                true
+            })?;
+        let mut sql = String::new();
+        if !imported {
+            for (id, name) in config.users.iter() {
+                sql.push_str(&format!(
+                    "INSERT OR IGNORE INTO users (user, name) VALUES ({}, '{}');",
+                    id,
+                    escape(name)
+                ));
+            }
+            for id in config.suspended.iter() {
+                sql.push_str(&format!("UPDATE users SET suspended = 1 WHERE user = {};", id));
+            }
+            for (id, weight) in config.weights.iter() {
+                sql.push_str(&format!(
+                    "UPDATE users SET weight = {} WHERE user = {};",
+                    weight, id
+                ));
+            }
+            if let Some(chat) = config.group_chat {
+                sql.push_str(&format!(
+                    "INSERT OR REPLACE INTO settings VALUES ('group_chat', '{}');",
+                    chat
+                ));
+            }
+            sql.push_str("INSERT INTO settings VALUES ('imported', '1');");
+        }
+        for (id, name) in config.admins.iter() {
+            sql.push_str(&format!(
+                "INSERT OR IGNORE INTO admins VALUES ({}, '{}');",
+                id,
+                escape(name)
+            ));
+        }
+        self.transaction(&sql)
+    }
+
+    pub fn roster(&mut self) -> Result<Roster, sqlite::Error> {
+        let mut roster = Roster {
+            admins: vec![],
+            users: vec![],
+            suspended: vec![],
+            weights: vec![],
+            group_chat: ChatId::new(0),
+        };
+        self.db
+            .iterate("SELECT user, name FROM admins ORDER BY rowid", |pairs| {
+                let id = UserId::new(pairs[0].1.unwrap().parse::<i64>().unwrap());
+                roster.admins.push((id, pairs[1].1.unwrap_or("").to_string()));
+                true
+            })?;
+        self.db.iterate(
+            "SELECT user, name, suspended, weight FROM users ORDER BY rowid",
+            |pairs| {
+                let id = UserId::new(pairs[0].1.unwrap().parse::<i64>().unwrap());
+                roster.users.push((id, pairs[1].1.unwrap_or("").to_string()));
+                if pairs[2].1 == Some("1") {
+                    roster.suspended.push(id);
+                }
+                let weight = pairs[3].1.unwrap().parse::<i64>().unwrap();
+                if weight != 1 {
+                    roster.weights.push((id, weight));
+                }
+                true
+            },
+        )?;
+        self.db
+            .iterate("SELECT value FROM settings WHERE key = 'group_chat'", |pairs| {
+                roster.group_chat = ChatId::new(pairs[0].1.unwrap().parse::<i64>().unwrap());
+                true
+            })?;
+        Ok(roster)
+    }
+
+    pub fn add_admin(&mut self, id: UserId, name: &str) -> Result<(), sqlite::Error> {
+        self.db.execute(&format!(
+            "INSERT OR IGNORE INTO admins VALUES ({}, '{}');",
+            id,
+            escape(name)
+        ))?;
+        Ok(())
+    }
+
+    pub fn add_user(&mut self, id: UserId, name: &str, weight: i64) -> Result<(), sqlite::Error> {
+        self.db.execute(&format!(
+            "INSERT OR IGNORE INTO users (user, name, weight) VALUES ({}, '{}', {});",
+            id,
+            escape(name),
+            weight
+        ))?;
+        Ok(())
+    }
+
+    pub fn remove_user(&mut self, id: UserId) -> Result<(), sqlite::Error> {
+        self.db
+            .execute(&format!("DELETE FROM users WHERE user = {};", id))?;
+        Ok(())
+    }
+
+    pub fn set_suspended(&mut self, id: UserId, suspended: bool) -> Result<(), sqlite::Error> {
+        self.db.execute(&format!(
+            "UPDATE users SET suspended = {} WHERE user = {};",
+            suspended as i64, id
+        ))?;
+        Ok(())
+    }
+
+    pub fn set_weight(&mut self, id: UserId, weight: i64) -> Result<(), sqlite::Error> {
+        self.db.execute(&format!(
+            "UPDATE users SET weight = {} WHERE user = {};",
+            weight, id
+        ))?;
+        Ok(())
+    }
+
+    pub fn set_group_chat(&mut self, chat: ChatId) -> Result<(), sqlite::Error> {
+        self.db.execute(&format!(
+            "INSERT OR REPLACE INTO settings VALUES ('group_chat', '{}');",
+            chat
+        ))?;
+        Ok(())
+    }
+
+    /// Every audit entry in order, as `(id, time, admin, action, detail,
+    /// hash)`.
+    pub fn audit_log(
+        &mut self,
+    ) -> Result<Vec<(i64, i64, UserId, String, String, String)>, sqlite::Error> {
+        let mut res = vec![];
+        self.db.iterate(
+            "SELECT id, time, admin, action, detail, hash FROM audit ORDER BY id",
+            |pairs| {
+                res.push((
+                    pairs[0].1.unwrap().parse::<i64>().unwrap(),
+                    pairs[1].1.unwrap().parse::<i64>().unwrap(),
+                    UserId::new(pairs[2].1.unwrap().parse::<i64>().unwrap()),
+                    pairs[3].1.unwrap_or("").to_string(),
+                    pairs[4].1.unwrap_or("").to_string(),
+                    pairs[5].1.unwrap_or("").to_string(),
+                ));
+                true
+            },
+        )?;
+        Ok(res)
+    }
+
+    /// Stored ballots per voter, as `(voter, ballots, weight)`. The weight
+    /// is `None` for a voter missing from `electors`.
+    pub fn ballot_counts(&mut self) -> Result<Vec<(UserId, i64, Option<i64>)>, sqlite::Error> {
+        let mut res = vec![];
+        self.db.iterate(
+            "SELECT votes.user, COUNT(*), electors.weight FROM votes
+            LEFT JOIN electors ON votes.user = electors.user
+            GROUP BY votes.user",
+            |pairs| {
+                res.push((
+                    UserId::new(pairs[0].1.unwrap().parse::<i64>().unwrap()),
+                    pairs[1].1.unwrap().parse::<i64>().unwrap(),
+                    pairs[2].1.map(|w| w.parse::<i64>().unwrap()),
+                ));
+                true
+            },
+        )?;
+        Ok(res)
+    }
+
     pub fn fetch_token(&mut self, id: UserId) -> Result<Vec<PollToken>, sqlite::Error> {
         let mut res = vec![];
         self.db
@@ -123,18 +978,20 @@ impl DbService {
         Ok(res)
     }
 
-    pub fn remove_token(&mut self, id: UserId, token: String) -> Result<(), sqlite::Error> {
-        self.db.execute(&format!(
-            "DELETE FROM votes WHERE user={} AND token='{}';",
-            id, token
-        ))?;
-        Ok(())
+    pub fn lang(&mut self, id: UserId) -> Result<Option<String>, sqlite::Error> {
+        let mut res = None;
+        self.db
+            .iterate(&format!("SELECT lang FROM langs WHERE user={}", id), |pairs| {
+                res = Some(pairs[0].1.unwrap().to_string());
+                true
+            })?;
+        Ok(res)
     }
 
-    pub fn insert_token(&mut self, token: PollToken) -> Result<(), sqlite::Error> {
+    pub fn set_lang(&mut self, id: UserId, lang: &str) -> Result<(), sqlite::Error> {
         self.db.execute(&format!(
-            "INSERT INTO votes VALUES ('{}', {}, {});",
-            token.token, token.user_id, token.msg_id
+            "INSERT OR REPLACE INTO langs VALUES ({}, '{}');",
+            id, lang
         ))?;
         Ok(())
     }
@@ -0,0 +1,72 @@
+use telegram_bot::types::{ChatId, UserId};
+
+/// Registered users, admins and the group chat. Loaded from the database at
+/// startup; every change is written back there as it is made.
+#[derive(Clone, Debug)]
+pub struct Roster {
+    pub admins: Vec<(UserId, String)>,
+    pub users: Vec<(UserId, String)>,
+    /// Registered users whose voting rights are currently suspended.
+    pub suspended: Vec<UserId>,
+    /// Voting weights other than the default of 1.
+    pub weights: Vec<(UserId, i64)>,
+    /// Chat registered with `/register_chat`, `0` until then.
+    pub group_chat: ChatId,
+}
+
+impl Roster {
+    pub fn add_admin(&mut self, id: UserId, name: String) {
+        self.admins.push((id, name));
+    }
+
+    pub fn add_user(&mut self, id: UserId, name: String) {
+        self.users.push((id, name));
+    }
+
+    pub fn has_user(&self, id: UserId) -> bool {
+        self.users.iter().any(|(u, _)| *u == id)
+    }
+
+    /// Registered users that are allowed to vote.
+    pub fn active_users(&self) -> Vec<(UserId, String)> {
+        self.users
+            .iter()
+            .filter(|(u, _)| !self.suspended.contains(u))
+            .cloned()
+            .collect()
+    }
+
+    pub fn remove_user(&mut self, id: UserId) -> bool {
+        let len = self.users.len();
+        self.users.retain(|(u, _)| *u != id);
+        self.suspended.retain(|u| *u != id);
+        self.weights.retain(|(u, _)| *u != id);
+        len != self.users.len()
+    }
+
+    pub fn weight(&self, id: UserId) -> i64 {
+        self.weights
+            .iter()
+            .find(|(u, _)| *u == id)
+            .map(|(_, w)| *w)
+            .unwrap_or(1)
+    }
+
+    pub fn set_weight(&mut self, id: UserId, weight: i64) {
+        self.weights.retain(|(u, _)| *u != id);
+        if weight != 1 {
+            self.weights.push((id, weight));
+        }
+    }
+
+    pub fn set_suspended(&mut self, id: UserId, suspended: bool) {
+        self.suspended.retain(|u| *u != id);
+        if suspended {
+            self.suspended.push(id);
+        }
+    }
+
+    pub fn set_group_chat(&mut self, id: ChatId) {
+        self.group_chat = id;
+    }
+}